@@ -0,0 +1,79 @@
+//! Scrape once, write the index as JSON and exit.
+//!
+//! Usage: `build-index --out index.json [--max-failures N]`
+//!
+//! Exits 1 if no weeks were parsed and 2 if more than `--max-failures` weeks
+//! (default 0) failed to download or extract, so cron can alert.
+
+use std::process::ExitCode;
+
+use cranbrook_catering_api::store::{FileMenuStore, MenuStore};
+use cranbrook_catering_api::{build_index_report, init_tracing};
+
+struct Args {
+    out: String,
+    max_failures: usize,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut out = None;
+    let mut max_failures = 0;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--out" => out = args.next(),
+            "--max-failures" => {
+                let value = args.next().ok_or("--max-failures needs a value")?;
+                max_failures = value
+                    .parse()
+                    .map_err(|_| format!("Invalid --max-failures value {value:?}"))?;
+            }
+            other => return Err(format!("Unknown argument {other:?}")),
+        }
+    }
+
+    Ok(Args {
+        out: out.ok_or("Missing required --out <path>")?,
+        max_failures,
+    })
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<ExitCode> {
+    init_tracing();
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("{err}\nUsage: build-index --out <path> [--max-failures N]");
+            return Ok(ExitCode::from(64));
+        }
+    };
+
+    let report = build_index_report().await?;
+
+    FileMenuStore::new(&args.out).store(&report.index).await?;
+    println!(
+        "Wrote {} entries from {} weeks to {}",
+        report.index.len(),
+        report.weeks_parsed,
+        args.out
+    );
+
+    if report.weeks_parsed == 0 {
+        eprintln!("No weeks were parsed");
+        return Ok(ExitCode::from(1));
+    }
+    if report.failures.len() > args.max_failures {
+        eprintln!(
+            "{} weeks failed (allowed {}):",
+            report.failures.len(),
+            args.max_failures
+        );
+        for (link, err) in &report.failures {
+            eprintln!("  {link}: {err}");
+        }
+        return Ok(ExitCode::from(2));
+    }
+    Ok(ExitCode::SUCCESS)
+}
@@ -0,0 +1,30 @@
+use cranbrook_catering_api::{
+    build_router, config, cors_layer, init_tracing, preload_current_weeks, run_refresher,
+};
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    init_tracing();
+    let config = config::install(config::Config::load()?);
+    let cors = cors_layer(&config)?;
+    let app = build_router(config.clone(), cors);
+
+    tokio::spawn(async {
+        match preload_current_weeks().await {
+            Ok(weeks) => tracing::info!("Preloaded weeks {weeks:?}"),
+            Err(err) => tracing::warn!("Preload failed: {err}"),
+        }
+    });
+
+    if config.refresh_interval_secs > 0 {
+        tokio::spawn(run_refresher(std::time::Duration::from_secs(
+            config.refresh_interval_secs,
+        )));
+    }
+
+    let addr = &config.bind_addr;
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    tracing::info!("Listening on {addr}");
+    axum::serve(listener, app).await?;
+    Ok(())
+}
@@ -0,0 +1,569 @@
+//! Process-wide cache of extracted PDF text and parsed weeks.
+//!
+//! Lives for the lifetime of the process, so warm Lambda invocations and the
+//! long-running server both skip repeat downloads.
+
+use chrono::NaiveDate;
+use lru::LruCache;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::num::NonZeroUsize;
+use std::sync::{Mutex, MutexGuard, OnceLock};
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_TEXT_CAPACITY: usize = 64;
+pub const DEFAULT_CACHE_TTL_SECS: u64 = 3600;
+pub const DEFAULT_STALE_MAX_AGE_SECS: u64 = 86400;
+
+#[derive(Clone)]
+pub struct CachedWeek {
+    pub link: String,
+    pub menus: HashMap<String, String>,
+    pub notes: crate::WeekNotes,
+    pub fetched_at: Instant,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Freshness {
+    Fresh,
+    /// Past the TTL but still servable while a background re-fetch runs.
+    Stale,
+    /// Too old to serve; callers must block on a fresh fetch.
+    Expired,
+}
+
+/// Stale-while-revalidate windows for cached entries.
+#[derive(Clone, Copy, Debug)]
+pub struct FreshnessPolicy {
+    pub ttl: Duration,
+    pub stale_max_age: Duration,
+}
+
+impl FreshnessPolicy {
+    pub fn classify(&self, age: Duration) -> Freshness {
+        if age < self.ttl {
+            Freshness::Fresh
+        } else if age < self.stale_max_age.max(self.ttl) {
+            Freshness::Stale
+        } else {
+            Freshness::Expired
+        }
+    }
+}
+
+/// The TTL windows from the configured `cache_ttl_secs` and `stale_max_age_secs`.
+pub fn freshness_policy() -> FreshnessPolicy {
+    let config = crate::config::config();
+    FreshnessPolicy {
+        ttl: Duration::from_secs(config.cache_ttl_secs),
+        stale_max_age: Duration::from_secs(config.stale_max_age_secs),
+    }
+}
+
+/// HTTP cache validators last seen for a URL, replayed on conditional requests.
+#[derive(Clone, Default, Serialize)]
+pub struct Validators {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct RefreshStatus {
+    pub at: String,
+    pub changed: bool,
+}
+
+/// Two or more links claiming the same week, and the one that was kept.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct WeekConflict {
+    pub menu_type: String,
+    pub week_start: String,
+    pub links: Vec<String>,
+    pub chosen: String,
+}
+
+#[derive(Serialize)]
+pub struct CachedWeekInfo {
+    pub week_start: String,
+    pub menu_type: String,
+    pub link: String,
+    pub entries: usize,
+    pub age_secs: u64,
+}
+
+#[derive(Serialize)]
+pub struct CacheSummary {
+    pub weeks_indexed: usize,
+    pub earliest: Option<String>,
+    pub latest: Option<String>,
+    pub total_entries: usize,
+    pub last_index_build: Option<String>,
+    pub current_week_covered: bool,
+    /// `ok`, or `degraded` when the last scrape found no menu links.
+    pub status: &'static str,
+    pub no_menus_found: bool,
+}
+
+#[derive(Serialize)]
+pub struct CacheDebug {
+    pub texts: Vec<String>,
+    pub weeks: Vec<CachedWeekInfo>,
+    pub validators: HashMap<String, Validators>,
+    pub last_refresh: Option<RefreshStatus>,
+    pub conflicts: Vec<WeekConflict>,
+    pub content_hashes: HashMap<String, String>,
+    /// The catering page each menu link was found on.
+    pub link_sources: HashMap<String, LinkSource>,
+}
+
+/// The catering page a menu link was found on, and that page's menu type.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct LinkSource {
+    pub page: String,
+    pub menu_type: String,
+}
+
+/// Extracted PDF texts are bounded by entry count and optionally total bytes,
+/// evicting least-recently-used entries. Parsed weeks are kept separately and
+/// survive eviction of their source text.
+pub struct MenuCache {
+    /// Catering pages by URL, with when each was fetched.
+    landing_pages: HashMap<String, (String, Instant)>,
+    texts: LruCache<String, String>,
+    text_bytes: usize,
+    max_text_bytes: Option<usize>,
+    /// Keyed by menu type as well, since each type has its own week.
+    weeks: HashMap<(NaiveDate, String), CachedWeek>,
+    validators: HashMap<String, Validators>,
+    last_refresh: Option<RefreshStatus>,
+    last_index_build: Option<String>,
+    revalidating: HashSet<String>,
+    conflicts: Vec<WeekConflict>,
+    content_hashes: HashMap<String, String>,
+    link_sources: HashMap<String, LinkSource>,
+    /// The week starts each PDF covers, kept after its text is evicted.
+    link_weeks: HashMap<String, Vec<NaiveDate>>,
+    /// Set when the catering pages were fetched but listed no menus.
+    no_menus_found: bool,
+}
+
+impl Default for MenuCache {
+    fn default() -> Self {
+        MenuCache::new(DEFAULT_TEXT_CAPACITY, None)
+    }
+}
+
+impl MenuCache {
+    pub fn new(text_capacity: usize, max_text_bytes: Option<usize>) -> Self {
+        MenuCache {
+            landing_pages: HashMap::new(),
+            texts: LruCache::new(NonZeroUsize::new(text_capacity).unwrap_or(NonZeroUsize::MIN)),
+            text_bytes: 0,
+            max_text_bytes,
+            weeks: HashMap::new(),
+            validators: HashMap::new(),
+            last_refresh: None,
+            last_index_build: None,
+            revalidating: HashSet::new(),
+            conflicts: Vec::new(),
+            content_hashes: HashMap::new(),
+            link_sources: HashMap::new(),
+            link_weeks: HashMap::new(),
+            no_menus_found: false,
+        }
+    }
+
+    pub fn landing_page(&self, url: &str) -> Option<String> {
+        self.landing_pages.get(url).map(|(html, _)| html.clone())
+    }
+
+    /// The cached landing page together with how long ago it was fetched.
+    pub fn landing_page_with_age(&self, url: &str) -> Option<(String, Duration)> {
+        self.landing_pages
+            .get(url)
+            .map(|(html, fetched_at)| (html.clone(), fetched_at.elapsed()))
+    }
+
+    pub fn set_landing_page(&mut self, url: &str, html: String) {
+        self.landing_pages
+            .insert(url.to_string(), (html, Instant::now()));
+    }
+
+    pub fn set_link_sources(&mut self, sources: HashMap<String, LinkSource>) {
+        self.link_sources = sources;
+    }
+
+    /// The menu type of the page `link` was found on; links from no known
+    /// page count as the primary menu type.
+    pub fn menu_type(&self, link: &str) -> String {
+        self.link_sources
+            .get(link)
+            .map(|source| source.menu_type.clone())
+            .unwrap_or_else(crate::primary_menu_type)
+    }
+
+    pub fn link_weeks(&self, link: &str) -> Option<Vec<NaiveDate>> {
+        self.link_weeks.get(link).cloned()
+    }
+
+    pub fn set_link_weeks(&mut self, link: &str, week_starts: Vec<NaiveDate>) {
+        self.link_weeks.insert(link.to_string(), week_starts);
+    }
+
+    pub fn no_menus_found(&self) -> bool {
+        self.no_menus_found
+    }
+
+    pub fn set_no_menus_found(&mut self, no_menus_found: bool) {
+        self.no_menus_found = no_menus_found;
+    }
+
+    pub fn text(&mut self, url: &str) -> Option<String> {
+        self.texts.get(url).cloned()
+    }
+
+    pub fn text_urls(&self) -> Vec<String> {
+        self.texts.iter().map(|(url, _)| url.clone()).collect()
+    }
+
+    pub fn insert_text(&mut self, url: String, text: String) {
+        self.text_bytes += text.len();
+        if let Some((_, old)) = self.texts.push(url, text) {
+            // Either the replaced value for the same URL or the evicted LRU entry.
+            self.text_bytes -= old.len();
+        }
+        if let Some(max_bytes) = self.max_text_bytes {
+            while self.text_bytes > max_bytes && self.texts.len() > 1 {
+                match self.texts.pop_lru() {
+                    Some((_, old)) => self.text_bytes -= old.len(),
+                    None => break,
+                }
+            }
+        }
+    }
+
+    /// Returns the parsed week only if it was parsed from the same link, so a
+    /// re-published PDF under a new URL is picked up.
+    pub fn week(&self, week_start: NaiveDate, link: &str) -> Option<CachedWeek> {
+        self.weeks
+            .get(&(week_start, self.menu_type(link)))
+            .filter(|week| week.link == link)
+            .cloned()
+    }
+
+    /// The distinct links the cached weeks were parsed from, sorted.
+    pub fn week_links(&self) -> Vec<String> {
+        let mut links: Vec<String> = self.weeks.values().map(|week| week.link.clone()).collect();
+        links.sort();
+        links.dedup();
+        links
+    }
+
+    pub fn weeks_for_link(&self, link: &str) -> Vec<NaiveDate> {
+        self.weeks
+            .iter()
+            .filter(|(_, week)| week.link == link)
+            .map(|((week_start, _), _)| *week_start)
+            .collect()
+    }
+
+    pub fn week_count(&self) -> usize {
+        self.weeks.len()
+    }
+
+    /// Every cached week's menus merged into one index, with keys of other
+    /// menu types than the primary one prefixed by their type.
+    pub fn cached_menus(&self) -> HashMap<String, String> {
+        let primary = crate::primary_menu_type();
+        let primary = primary.as_str();
+        self.weeks
+            .iter()
+            .flat_map(|((_, menu_type), week)| {
+                week.menus.iter().map(move |(key, meal)| {
+                    (crate::menu_key(menu_type, key, primary), meal.clone())
+                })
+            })
+            .collect()
+    }
+
+    pub fn insert_week(&mut self, week_start: NaiveDate, week: CachedWeek) {
+        let menu_type = self.menu_type(&week.link);
+        self.weeks.insert((week_start, menu_type), week);
+    }
+
+    /// SHA-256 (hex) of the PDF bytes last extracted for `url`.
+    pub fn content_hash(&self, url: &str) -> Option<String> {
+        self.content_hashes.get(url).cloned()
+    }
+
+    pub fn set_content_hash(&mut self, url: &str, hash: String) {
+        self.content_hashes.insert(url.to_string(), hash);
+    }
+
+    /// Marks every week parsed from `link` as freshly validated, e.g. after a 304.
+    pub fn touch_weeks_for_link(&mut self, link: &str) {
+        for week in self.weeks.values_mut().filter(|week| week.link == link) {
+            week.fetched_at = Instant::now();
+        }
+    }
+
+    /// Claims a background re-fetch for `key`; false if one is already running.
+    pub fn begin_revalidation(&mut self, key: &str) -> bool {
+        self.revalidating.insert(key.to_string())
+    }
+
+    pub fn end_revalidation(&mut self, key: &str) {
+        self.revalidating.remove(key);
+    }
+
+    pub fn validators(&self, url: &str) -> Validators {
+        self.validators.get(url).cloned().unwrap_or_default()
+    }
+
+    pub fn set_validators(&mut self, url: &str, validators: Validators) {
+        self.validators.insert(url.to_string(), validators);
+    }
+
+    pub fn record_refresh(&mut self, changed: bool) {
+        self.last_refresh = Some(RefreshStatus {
+            at: chrono::Local::now().to_rfc3339(),
+            changed,
+        });
+    }
+
+    /// Replaces the duplicate-week conflicts seen on the latest catering page scan.
+    pub fn set_conflicts(&mut self, conflicts: Vec<WeekConflict>) {
+        self.conflicts = conflicts;
+    }
+
+    pub fn record_index_build(&mut self) {
+        self.last_index_build = Some(chrono::Local::now().to_rfc3339());
+    }
+
+    /// Aggregates the parsed weeks; `latest` is the last day of the newest week.
+    pub fn summary(&self, today: NaiveDate) -> CacheSummary {
+        let format = |date: NaiveDate| date.format("%Y-%m-%d").to_string();
+        let earliest = self.weeks.keys().map(|(week_start, _)| *week_start).min();
+        let latest = self.weeks.keys().map(|(week_start, _)| *week_start).max();
+
+        CacheSummary {
+            weeks_indexed: self.weeks.len(),
+            earliest: earliest.map(format),
+            latest: latest.map(|week_start| format(week_start + chrono::Duration::days(6))),
+            total_entries: self.weeks.values().map(|week| week.menus.len()).sum(),
+            last_index_build: self.last_index_build.clone(),
+            current_week_covered: self.weeks.keys().any(|(week_start, _)| {
+                today >= *week_start && today <= *week_start + chrono::Duration::days(6)
+            }),
+            status: if self.no_menus_found {
+                "degraded"
+            } else {
+                "ok"
+            },
+            no_menus_found: self.no_menus_found,
+        }
+    }
+
+    pub fn debug_snapshot(&self) -> CacheDebug {
+        let mut texts = self.text_urls();
+        texts.sort();
+        let mut weeks: Vec<CachedWeekInfo> = self
+            .weeks
+            .iter()
+            .map(|((week_start, menu_type), week)| CachedWeekInfo {
+                week_start: week_start.format("%Y-%m-%d").to_string(),
+                menu_type: menu_type.clone(),
+                link: week.link.clone(),
+                entries: week.menus.len(),
+                age_secs: week.fetched_at.elapsed().as_secs(),
+            })
+            .collect();
+        weeks.sort_by(|a, b| a.week_start.cmp(&b.week_start));
+
+        CacheDebug {
+            texts,
+            weeks,
+            validators: self.validators.clone(),
+            last_refresh: self.last_refresh.clone(),
+            conflicts: self.conflicts.clone(),
+            content_hashes: self.content_hashes.clone(),
+            link_sources: self.link_sources.clone(),
+        }
+    }
+}
+
+pub fn lock_cache() -> MutexGuard<'static, MenuCache> {
+    static CACHE: OnceLock<Mutex<MenuCache>> = OnceLock::new();
+    CACHE
+        .get_or_init(|| {
+            let config = crate::config::config();
+            let max_bytes = Some(config.text_cache_max_bytes).filter(|bytes| *bytes > 0);
+            Mutex::new(MenuCache::new(config.text_cache_capacity, max_bytes))
+        })
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn week(link: &str) -> CachedWeek {
+        CachedWeek {
+            link: link.to_string(),
+            menus: HashMap::from([("2026-01-26-lunch".to_string(), "Pasta".to_string())]),
+            notes: crate::WeekNotes::default(),
+            fetched_at: Instant::now(),
+        }
+    }
+
+    #[test]
+    fn test_freshness_policy_windows() {
+        let policy = FreshnessPolicy {
+            ttl: Duration::from_secs(60),
+            stale_max_age: Duration::from_secs(600),
+        };
+        assert_eq!(policy.classify(Duration::from_secs(10)), Freshness::Fresh);
+        assert_eq!(policy.classify(Duration::from_secs(60)), Freshness::Stale);
+        assert_eq!(policy.classify(Duration::from_secs(599)), Freshness::Stale);
+        assert_eq!(
+            policy.classify(Duration::from_secs(600)),
+            Freshness::Expired
+        );
+
+        // A stale window shorter than the TTL means nothing is ever served stale.
+        let strict = FreshnessPolicy {
+            ttl: Duration::from_secs(60),
+            stale_max_age: Duration::ZERO,
+        };
+        assert_eq!(strict.classify(Duration::from_secs(60)), Freshness::Expired);
+    }
+
+    #[test]
+    fn test_revalidation_is_claimed_once() {
+        let mut cache = MenuCache::default();
+        assert!(cache.begin_revalidation("a.pdf"));
+        assert!(!cache.begin_revalidation("a.pdf"));
+        cache.end_revalidation("a.pdf");
+        assert!(cache.begin_revalidation("a.pdf"));
+    }
+
+    #[test]
+    fn test_text_cache_evicts_least_recently_used() {
+        let mut cache = MenuCache::new(2, None);
+        let week_start = NaiveDate::from_ymd_opt(2026, 1, 26).unwrap();
+        cache.insert_week(week_start, week("a.pdf"));
+
+        cache.insert_text("a.pdf".to_string(), "a".to_string());
+        cache.insert_text("b.pdf".to_string(), "b".to_string());
+        // Touch "a" so "b" becomes least recently used.
+        assert!(cache.text("a.pdf").is_some());
+        cache.insert_text("c.pdf".to_string(), "c".to_string());
+
+        assert!(cache.text("b.pdf").is_none());
+        assert!(cache.text("a.pdf").is_some());
+        assert!(cache.text("c.pdf").is_some());
+
+        cache.insert_text("d.pdf".to_string(), "d".to_string());
+        cache.insert_text("e.pdf".to_string(), "e".to_string());
+        assert!(cache.text("a.pdf").is_none());
+
+        // The parsed week outlives its evicted source text.
+        let cached = cache.week(week_start, "a.pdf").unwrap();
+        assert_eq!(cached.menus["2026-01-26-lunch"], "Pasta");
+        // ...and is still revalidated by the refresher.
+        assert_eq!(cache.week_links(), ["a.pdf"]);
+    }
+
+    #[test]
+    fn test_summary() {
+        let mut cache = MenuCache::default();
+        let today = NaiveDate::from_ymd_opt(2026, 1, 28).unwrap();
+        assert_eq!(cache.summary(today).weeks_indexed, 0);
+        assert!(!cache.summary(today).current_week_covered);
+
+        cache.insert_week(NaiveDate::from_ymd_opt(2026, 1, 19).unwrap(), week("a.pdf"));
+        cache.insert_week(NaiveDate::from_ymd_opt(2026, 1, 26).unwrap(), week("b.pdf"));
+        cache.record_index_build();
+
+        let summary = cache.summary(today);
+        assert_eq!(summary.weeks_indexed, 2);
+        assert_eq!(summary.earliest.as_deref(), Some("2026-01-19"));
+        assert_eq!(summary.latest.as_deref(), Some("2026-02-01"));
+        assert_eq!(summary.total_entries, 2);
+        assert!(summary.last_index_build.is_some());
+        assert!(summary.current_week_covered);
+    }
+
+    #[test]
+    fn test_summary_degraded_when_no_menus_found() {
+        let today = NaiveDate::from_ymd_opt(2026, 1, 28).unwrap();
+        let mut cache = MenuCache::default();
+        assert_eq!(cache.summary(today).status, "ok");
+
+        cache.set_no_menus_found(true);
+        let summary = cache.summary(today);
+        assert_eq!(summary.status, "degraded");
+        assert!(summary.no_menus_found);
+    }
+
+    #[test]
+    fn test_cached_menus_merges_weeks() {
+        let mut cache = MenuCache::default();
+        assert_eq!(cache.week_count(), 0);
+
+        let mut next = week("b.pdf");
+        next.menus = HashMap::from([("2026-02-02-lunch".to_string(), "Curry".to_string())]);
+        cache.insert_week(NaiveDate::from_ymd_opt(2026, 1, 26).unwrap(), week("a.pdf"));
+        cache.insert_week(NaiveDate::from_ymd_opt(2026, 2, 2).unwrap(), next);
+
+        let menus = cache.cached_menus();
+        assert_eq!(cache.week_count(), 2);
+        assert_eq!(cache.week_links(), ["a.pdf", "b.pdf"]);
+        assert_eq!(menus["2026-01-26-lunch"], "Pasta");
+        assert_eq!(menus["2026-02-02-lunch"], "Curry");
+    }
+
+    #[test]
+    fn test_menu_types_keep_their_own_weeks() {
+        let mut cache = MenuCache::default();
+        cache.set_link_sources(HashMap::from([(
+            "prep.pdf".to_string(),
+            LinkSource {
+                page: "https://x/prep/catering/".to_string(),
+                menu_type: "prep".to_string(),
+            },
+        )]));
+        let week_start = NaiveDate::from_ymd_opt(2026, 1, 26).unwrap();
+        let mut prep = week("prep.pdf");
+        prep.menus = HashMap::from([("2026-01-26-lunch".to_string(), "Fish fingers".to_string())]);
+        cache.insert_week(week_start, week("senior.pdf"));
+        cache.insert_week(week_start, prep);
+
+        assert_eq!(cache.week_count(), 2);
+        assert!(cache.week(week_start, "senior.pdf").is_some());
+        assert!(cache.week(week_start, "prep.pdf").is_some());
+        let menus = cache.cached_menus();
+        assert_eq!(menus["2026-01-26-lunch"], "Pasta");
+        assert_eq!(menus["prep:2026-01-26-lunch"], "Fish fingers");
+
+        // A re-published senior PDF still replaces the old one's week.
+        cache.insert_week(week_start, week("senior-v2.pdf"));
+        assert_eq!(cache.week_count(), 2);
+        assert!(cache.week(week_start, "senior.pdf").is_none());
+    }
+
+    #[test]
+    fn test_text_cache_respects_byte_limit() {
+        let mut cache = MenuCache::new(10, Some(10));
+        cache.insert_text("a.pdf".to_string(), "x".repeat(6));
+        cache.insert_text("b.pdf".to_string(), "y".repeat(6));
+
+        assert!(cache.text("a.pdf").is_none());
+        assert!(cache.text("b.pdf").is_some());
+        assert_eq!(cache.text_bytes, 6);
+
+        cache.insert_text("b.pdf".to_string(), "z".repeat(2));
+        assert_eq!(cache.text_bytes, 2);
+    }
+}
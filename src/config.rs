@@ -0,0 +1,254 @@
+//! Typed service configuration.
+//!
+//! Loaded once from an optional `config.toml` (or the file named by
+//! `CONFIG_FILE`), overlaid by environment variables of the same names in
+//! upper case, e.g. `BIND_ADDR` or `CACHE_TTL_SECS`. Every field has a
+//! default, so running with no configuration at all still works.
+
+use figment::{
+    Figment,
+    providers::{Env, Format, Serialized, Toml},
+};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::sync::{Arc, OnceLock};
+
+pub const DEFAULT_CONFIG_FILE: &str = "config.toml";
+
+/// Environment variables read into `Config`; other variables are ignored.
+const ENV_KEYS: [&str; 34] = [
+    "CATERING_PAGE_URL",
+    "CATERING_PAGE_URLS",
+    "BIND_ADDR",
+    "CACHE_TTL_SECS",
+    "STALE_MAX_AGE_SECS",
+    "REFRESH_INTERVAL_SECS",
+    "REQUEST_TIMEOUT_SECS",
+    "CORS_ORIGINS",
+    "DEBUG_ENDPOINTS",
+    "BRUNCH_FALLBACK_TEXT",
+    "EXPECTED_SECTION_ORDER",
+    "INDEX_PATH",
+    "INDEX_BUCKET",
+    "INDEX_KEY",
+    "MAX_INFERENCE_WEEKS",
+    "MAX_BODY_BYTES",
+    "TIMEZONE",
+    "WEEK_START_DAY",
+    "PUBLISH_LOOKAHEAD_DAYS",
+    "MAX_BATCH_SIZE",
+    "TERM_DATES",
+    "ALLOWED_PDF_HOSTS",
+    "USER_AGENT",
+    "CONTACT_EMAIL",
+    "DATE_OUTPUT_FORMAT",
+    "CACHE_DIR",
+    "MAX_WEEKS",
+    "TEXT_CACHE_CAPACITY",
+    "TEXT_CACHE_MAX_BYTES",
+    "BREAKFAST_HEADERS",
+    "BREAK_HEADERS",
+    "BRUNCH_HEADERS",
+    "LUNCH_HEADERS",
+    "DINNER_HEADERS",
+];
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    pub catering_page_url: String,
+    /// Comma-separated catering pages (e.g. one per site) scraped and merged,
+    /// each optionally `menu_type=url`; empty scrapes `catering_page_url` alone.
+    pub catering_page_urls: String,
+    pub bind_addr: String,
+    pub cache_ttl_secs: u64,
+    pub stale_max_age_secs: u64,
+    /// Background refresh interval for `serve`; 0 disables the refresher.
+    pub refresh_interval_secs: u64,
+    pub request_timeout_secs: u64,
+    /// Comma-separated allowed origins, or `*` for any.
+    pub cors_origins: String,
+    #[serde(deserialize_with = "flag")]
+    pub debug_endpoints: bool,
+    /// Served for a brunch header with no parsed content; empty omits the entry.
+    pub brunch_fallback_text: String,
+    /// Comma-separated periods in the order PDF sections are expected to appear.
+    pub expected_section_order: String,
+    /// Where the scheduled refresh writes the built index.
+    pub index_path: String,
+    /// With the `s3` feature, a non-empty bucket stores the index at
+    /// `index_key` in S3 instead of at `index_path`.
+    pub index_bucket: String,
+    pub index_key: String,
+    /// How far from the inferred target week a published week may be and
+    /// still be served; further away answers 404 instead. Unlimited by default.
+    pub max_inference_weeks: u32,
+    /// Larger request bodies are rejected with 413.
+    pub max_body_bytes: usize,
+    /// Zone "today" is computed in: `Europe/London`, `UTC`, `local` or a fixed
+    /// offset such as `+01:00`.
+    pub timezone: String,
+    /// Weekday menu weeks start on, e.g. `Mon` or `Sunday`.
+    pub week_start_day: String,
+    /// Days past the latest published week still treated as "not published yet".
+    pub publish_lookahead_days: i64,
+    /// Largest accepted `POST /meals` batch.
+    pub max_batch_size: usize,
+    /// Comma-separated `name:start:end` term entries.
+    pub term_dates: String,
+    /// Comma-separated hosts PDFs may be fetched from besides the school's own.
+    pub allowed_pdf_hosts: String,
+    /// Replaces the default user agent when set.
+    pub user_agent: String,
+    /// Sent as the `From` header when set.
+    pub contact_email: String,
+    /// `iso`, `dmy` or a strftime pattern for dates in responses.
+    pub date_output_format: String,
+    /// Directory raw PDFs are cached in; empty uses a temp subdirectory.
+    pub cache_dir: String,
+    /// How many PDFs a full scrape processes, nearest first; 0 is unbounded.
+    pub max_weeks: usize,
+    pub text_cache_capacity: usize,
+    /// Total bytes of cached PDF text; 0 is unbounded.
+    pub text_cache_max_bytes: usize,
+    /// Comma-separated synonyms added to each section header's defaults.
+    pub breakfast_headers: String,
+    pub break_headers: String,
+    pub brunch_headers: String,
+    pub lunch_headers: String,
+    pub dinner_headers: String,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            catering_page_url: crate::CATERING_PAGE_URL.to_string(),
+            catering_page_urls: String::new(),
+            bind_addr: "0.0.0.0:3000".to_string(),
+            cache_ttl_secs: crate::cache::DEFAULT_CACHE_TTL_SECS,
+            stale_max_age_secs: crate::cache::DEFAULT_STALE_MAX_AGE_SECS,
+            refresh_interval_secs: 3600,
+            request_timeout_secs: 30,
+            cors_origins: "*".to_string(),
+            debug_endpoints: false,
+            brunch_fallback_text: String::new(),
+            expected_section_order: "breakfast,break,brunch,lunch,dinner".to_string(),
+            index_path: "index.json".to_string(),
+            index_bucket: String::new(),
+            index_key: "index.json".to_string(),
+            max_inference_weeks: u32::MAX,
+            max_body_bytes: 64 * 1024,
+            timezone: "Europe/London".to_string(),
+            week_start_day: "Mon".to_string(),
+            publish_lookahead_days: crate::DEFAULT_PUBLISH_LOOKAHEAD_DAYS,
+            max_batch_size: crate::DEFAULT_MAX_BATCH_SIZE,
+            term_dates: crate::DEFAULT_TERM_DATES.to_string(),
+            allowed_pdf_hosts: String::new(),
+            user_agent: String::new(),
+            contact_email: String::new(),
+            date_output_format: String::new(),
+            cache_dir: String::new(),
+            max_weeks: 0,
+            text_cache_capacity: crate::cache::DEFAULT_TEXT_CAPACITY,
+            text_cache_max_bytes: 0,
+            breakfast_headers: String::new(),
+            break_headers: String::new(),
+            brunch_headers: String::new(),
+            lunch_headers: String::new(),
+            dinner_headers: String::new(),
+        }
+    }
+}
+
+impl Config {
+    /// Reads `CONFIG_FILE` (default `config.toml`, skipped if missing) and the environment.
+    pub fn load() -> Result<Config, figment::Error> {
+        let path = std::env::var("CONFIG_FILE").unwrap_or_else(|_| DEFAULT_CONFIG_FILE.to_string());
+        Self::defaults()
+            .merge(Toml::file(path))
+            .merge(Env::raw().only(&ENV_KEYS))
+            .extract()
+    }
+
+    /// Defaults overlaid by TOML text, without consulting the environment.
+    pub fn from_toml_str(toml: &str) -> Result<Config, figment::Error> {
+        Self::defaults().merge(Toml::string(toml)).extract()
+    }
+
+    fn defaults() -> Figment {
+        Figment::from(Serialized::defaults(Config::default()))
+    }
+}
+
+/// Accepts `true`/`false` as well as the `1`/`0` the env vars have always taken.
+fn flag<'de, D: Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Flag {
+        Bool(bool),
+        Int(i64),
+        Str(String),
+    }
+
+    Ok(match Flag::deserialize(deserializer)? {
+        Flag::Bool(value) => value,
+        Flag::Int(value) => value != 0,
+        Flag::Str(value) => matches!(value.trim().to_lowercase().as_str(), "1" | "true"),
+    })
+}
+
+static CONFIG: OnceLock<Arc<Config>> = OnceLock::new();
+
+/// Makes `config` the process-wide configuration. The first call wins; later
+/// calls return the configuration already installed.
+pub fn install(config: Config) -> Arc<Config> {
+    CONFIG.get_or_init(|| Arc::new(config)).clone()
+}
+
+/// The installed configuration, loading it on first use if `main` didn't.
+pub fn config() -> Arc<Config> {
+    CONFIG
+        .get_or_init(|| {
+            Arc::new(Config::load().unwrap_or_else(|err| {
+                tracing::warn!("Invalid configuration ({err}); using defaults");
+                Config::default()
+            }))
+        })
+        .clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_empty_config_uses_defaults() {
+        assert_eq!(Config::from_toml_str("").unwrap(), Config::default());
+    }
+
+    #[test]
+    fn test_toml_overrides_defaults() {
+        let config = Config::from_toml_str(
+            "bind_addr = \"127.0.0.1:8080\"\ncache_ttl_secs = 60\ndebug_endpoints = 1\n",
+        )
+        .unwrap();
+
+        assert_eq!(config.bind_addr, "127.0.0.1:8080");
+        assert_eq!(config.cache_ttl_secs, 60);
+        assert!(config.debug_endpoints);
+        assert_eq!(config.cors_origins, "*");
+    }
+
+    #[test]
+    fn test_toml_sets_formerly_env_only_settings() {
+        let config = Config::from_toml_str(
+            "timezone = \"UTC\"\nmax_batch_size = 5\nlunch_headers = \"luncheon\"\ntext_cache_max_bytes = 1024\n",
+        )
+        .unwrap();
+
+        assert_eq!(config.timezone, "UTC");
+        assert_eq!(config.max_batch_size, 5);
+        assert_eq!(config.lunch_headers, "luncheon");
+        assert_eq!(config.text_cache_max_bytes, 1024);
+        assert_eq!(config.term_dates, crate::DEFAULT_TERM_DATES);
+    }
+}
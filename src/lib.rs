@@ -1,17 +1,113 @@
-use axum::{extract::Query, http::StatusCode, response::IntoResponse};
-use chrono::{Datelike, Local, Month, NaiveDate};
+pub mod cache;
+pub mod config;
+pub mod request_id;
+pub mod store;
+
+use axum::{
+    Router,
+    extract::{FromRequestParts, Query, State},
+    http::{HeaderValue, Method, StatusCode, header},
+    response::IntoResponse,
+    routing::get,
+};
+use cache::{
+    CachedWeek, Freshness, LinkSource, Validators, WeekConflict, freshness_policy, lock_cache,
+};
+use chrono::{Datelike, Local, Month, NaiveDate, Weekday};
 use lambda_runtime::LambdaEvent;
 use reqwest::Client;
 use reqwest::Url;
 use scraper::{Html, Selector};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
+use std::sync::Arc;
+use store::MenuStore;
+use tower_http::cors::{AllowOrigin, CorsLayer};
+use tower_http::limit::RequestBodyLimitLayer;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MealPeriod {
+    Breakfast,
+    /// Mid-morning break / tuck item, on menus that list one.
+    Break,
+    Brunch,
+    Lunch,
+    Dinner,
+}
+
+impl MealPeriod {
+    pub const ALL: [MealPeriod; 5] = [
+        MealPeriod::Breakfast,
+        MealPeriod::Break,
+        MealPeriod::Brunch,
+        MealPeriod::Lunch,
+        MealPeriod::Dinner,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MealPeriod::Breakfast => "breakfast",
+            MealPeriod::Break => "break",
+            MealPeriod::Brunch => "brunch",
+            MealPeriod::Lunch => "lunch",
+            MealPeriod::Dinner => "dinner",
+        }
+    }
+}
+
+impl FromStr for MealPeriod {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if ["morning break", "morning-break", "elevenses"]
+            .iter()
+            .any(|alias| alias.eq_ignore_ascii_case(s))
+        {
+            return Ok(MealPeriod::Break);
+        }
+        MealPeriod::ALL
+            .into_iter()
+            .find(|period| period.as_str().eq_ignore_ascii_case(s))
+            .ok_or(())
+    }
+}
+
+/// Parses a comma- or slash-separated period list, returning the unrecognized entries on failure.
+pub fn parse_periods(input: &str) -> Result<Vec<MealPeriod>, Vec<String>> {
+    let mut periods = Vec::new();
+    let mut unknown = Vec::new();
+    for part in input
+        .split([',', '/'])
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+    {
+        match part.parse::<MealPeriod>() {
+            Ok(period) if !periods.contains(&period) => periods.push(period),
+            Ok(_) => {}
+            Err(()) => unknown.push(part.to_string()),
+        }
+    }
+    if !unknown.is_empty() {
+        return Err(unknown);
+    }
+    if periods.is_empty() {
+        return Err(vec![input.to_string()]);
+    }
+    Ok(periods)
+}
 
 #[derive(Deserialize)]
 pub struct QueryParams {
     pub date: String,
     pub period: String,
+    #[serde(default)]
+    pub explain: bool,
+    /// `prev` falls back to the previous published week's same weekday.
+    #[serde(default)]
+    pub fallback: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -19,517 +115,6195 @@ pub struct MealResponse {
     pub date: String,
     pub period: String,
     pub meal: String,
+    /// "exact" when the week contains the requested date, otherwise "inferred".
+    pub resolution: &'static str,
+    /// The week the meal was taken from, only for inferred resolutions.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub week_start: Option<String>,
 }
 
-pub async fn get_meal(Query(params): Query<QueryParams>) -> impl IntoResponse {
-    let date = match parse_date_param(&params.date) {
-        Some(date) => date,
-        None => {
-            return (
-                StatusCode::BAD_REQUEST,
-                "Invalid date format. Use YYYY-MM-DD or YYYY/MM/DD.",
-            )
-                .into_response();
+/// How a requested date was matched to a published week.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WeekResolution {
+    /// The published week contains the requested date.
+    Exact(NaiveDate),
+    /// No published week contains the date; this nearby week was inferred.
+    Inferred(NaiveDate),
+    /// The resolved week lacked the period, so the same weekday was taken from
+    /// this earlier week (`?fallback=prev`).
+    PreviousWeekFallback(NaiveDate),
+}
+
+impl WeekResolution {
+    pub fn week_start(self) -> NaiveDate {
+        match self {
+            WeekResolution::Exact(week_start)
+            | WeekResolution::Inferred(week_start)
+            | WeekResolution::PreviousWeekFallback(week_start) => week_start,
         }
-    };
-    let period = params.period.to_lowercase();
-    let fetched = fetch_meal_for_date(date, &period).await;
+    }
 
-    match fetched {
-        Ok(Some(meal)) => axum::Json(MealResponse {
-            date: format_date(date),
-            period,
-            meal,
-        })
-        .into_response(),
-        Ok(None) => (
-            StatusCode::NOT_FOUND,
-            format!("Meal not found for {} {}", format_date(date), period),
-        )
-            .into_response(),
-        Err(err) => (
-            StatusCode::BAD_GATEWAY,
-            format!("Failed to fetch menu data: {err}"),
-        )
-            .into_response(),
+    pub fn as_str(self) -> &'static str {
+        match self {
+            WeekResolution::Exact(_) => "exact",
+            WeekResolution::Inferred(_) => "inferred",
+            WeekResolution::PreviousWeekFallback(_) => "previous_week_fallback",
+        }
     }
 }
 
-pub fn build_client() -> anyhow::Result<Client> {
-    let client = Client::builder()
-        // Avoid macOS system proxy lookup that can panic in sandboxed contexts.
-        .no_proxy()
-        .user_agent("cranbrook-catering-api/0.1")
-        .build()?;
-    Ok(client)
+pub const DEFAULT_MAX_BATCH_SIZE: usize = 100;
+
+#[derive(Deserialize)]
+pub struct BatchItem {
+    pub date: String,
+    pub period: String,
 }
 
-pub async fn fetch_menu_links(client: &Client) -> anyhow::Result<Vec<(String, Option<NaiveDate>)>> {
-    let resp = client
-        .get("https://www.cranbrookschool.co.uk/school-information/cranbrook-catering/")
-        .send()
-        .await?
-        .text()
-        .await?;
+#[derive(Serialize)]
+pub struct BatchResult {
+    pub date: String,
+    pub period: String,
+    pub meal: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
 
-    let doc = Html::parse_document(&resp);
-    let selector = Selector::parse("a").unwrap();
-    let base = Url::parse("https://www.cranbrookschool.co.uk/")?;
+#[derive(Serialize)]
+pub struct MultiMealResponse {
+    pub date: String,
+    pub meals: BTreeMap<String, Option<String>>,
+}
 
-    let mut links = Vec::new();
-    for element in doc.select(&selector) {
-        if let Some(href) = element.value().attr("href") {
-            let href_lower = href.to_lowercase();
-            if href_lower.contains(".pdf") && href_lower.contains("menu") {
-                let link = match base.join(href) {
-                    Ok(link) => link,
-                    Err(_) => continue,
-                };
-                let link_text = element.text().collect::<String>();
-                let week_date = parse_week_commencing(&link_text);
-                links.push((link.to_string(), week_date));
-            }
-        }
+#[derive(Serialize)]
+pub struct CandidateMeal {
+    pub week_start: String,
+    pub meal: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct MealExplanation {
+    pub date: String,
+    pub period: String,
+    pub week_start: String,
+    /// "exact" when the week contains the requested date, otherwise "inferred".
+    pub resolution: &'static str,
+    pub meal: Option<String>,
+    /// Meals from the neighbouring published weeks, only for inferred resolutions.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub candidates: Vec<CandidateMeal>,
+}
+
+#[derive(Serialize)]
+pub struct PeriodMeal {
+    pub period: String,
+    pub items: Vec<String>,
+    /// `items` with their dietary markers and allergens pulled out.
+    pub dishes: Vec<Dish>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Dish {
+    pub name: String,
+    /// Normalised markers such as "vegetarian" or "gluten_free".
+    pub dietary: Vec<String>,
+    /// Lower-cased allergens from a "(contains ...)" note.
+    pub allergens: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct DayMenu {
+    pub date: String,
+    pub weekday: String,
+    pub meals: Vec<PeriodMeal>,
+    /// Themed or special day flagged in the day's dishes, e.g. "Burns Night".
+    pub theme: Option<String>,
+}
+
+/// One weekday of `/week/grid`: its ISO date plus each period's items.
+#[derive(Serialize)]
+pub struct GridDay {
+    pub date: String,
+    #[serde(flatten)]
+    pub meals: BTreeMap<String, Vec<String>>,
+}
+
+/// A week keyed by weekday name, serialized as a JSON object in week order.
+pub struct WeekGrid(pub Vec<(String, GridDay)>);
+
+impl Serialize for WeekGrid {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.0.iter().map(|(weekday, day)| (weekday, day)))
     }
+}
 
-    Ok(links)
+/// Week-level context from the PDF, such as "menu subject to change" disclaimers.
+#[derive(Clone, Default, Serialize)]
+pub struct WeekNotes {
+    pub notes: Vec<String>,
+    pub last_updated: Option<String>,
 }
 
-pub fn parse_week_commencing(text: &str) -> Option<NaiveDate> {
-    // Parse "Menu for w/c Monday 26th January 2026" format
-    let re = regex::Regex::new(r"w/c\s+\w+\s+(\d+)(?:st|nd|rd|th)?\s+(\w+)\s+(\d{4})").ok()?;
-    let caps = re.captures(text)?;
+#[derive(Serialize)]
+pub struct WeekNeighbor {
+    pub week_start: String,
+    pub published: bool,
+}
 
-    let day = caps.get(1)?.as_str().parse::<u32>().ok()?;
-    let month_str = caps.get(2)?.as_str();
-    let year = caps.get(3)?.as_str().parse::<i32>().ok()?;
+#[derive(Serialize)]
+pub struct WeekResponse {
+    pub week_start: String,
+    pub days: Vec<DayMenu>,
+    #[serde(flatten)]
+    pub notes: WeekNotes,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prev_week: Option<WeekNeighbor>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_week: Option<WeekNeighbor>,
+}
 
-    let month: u32 = match month_str.to_lowercase().as_str() {
-        "january" => Month::January,
-        "february" => Month::February,
-        "march" => Month::March,
-        "april" => Month::April,
-        "may" => Month::May,
-        "june" => Month::June,
-        "july" => Month::July,
-        "august" => Month::August,
-        "september" => Month::September,
-        "october" => Month::October,
-        "november" => Month::November,
-        "december" => Month::December,
-        _ => return None,
-    } as u32;
+#[derive(Serialize)]
+pub struct DayResponse {
+    pub date: String,
+    pub weekday: String,
+    pub week_start: String,
+    pub meals: Vec<PeriodMeal>,
+    pub theme: Option<String>,
+    #[serde(flatten)]
+    pub notes: WeekNotes,
+}
 
-    NaiveDate::from_ymd_opt(year, month, day)
+#[derive(Deserialize)]
+pub struct WeekParams {
+    pub date: String,
+    #[serde(default)]
+    pub nav: bool,
 }
 
-pub fn parse_week_commencing_from_pdf_text(text: &str) -> Option<NaiveDate> {
-    // Handles variants such as:
-    // - "Week Commencing Monday 26th January 2026"
-    // - "w/c Monday 26 January 2026"
-    let re = regex::Regex::new(
-        r"(?i)(?:week\s+commencing|w/c)\s+\w+\s+(\d+)(?:st|nd|rd|th)?\s+(\w+)\s+(\d{4})",
-    )
-    .ok()?;
-    let caps = re.captures(text)?;
+#[derive(Deserialize)]
+pub struct DateParams {
+    pub date: String,
+}
 
-    let day = caps.get(1)?.as_str().parse::<u32>().ok()?;
-    let month_str = caps.get(2)?.as_str();
-    let year = caps.get(3)?.as_str().parse::<i32>().ok()?;
+#[derive(Serialize)]
+pub struct PeriodCoverage {
+    pub period: String,
+    pub filled: usize,
+    pub expected: usize,
+}
 
-    let month: u32 = match month_str.to_lowercase().as_str() {
-        "january" => Month::January,
-        "february" => Month::February,
-        "march" => Month::March,
-        "april" => Month::April,
-        "may" => Month::May,
-        "june" => Month::June,
-        "july" => Month::July,
-        "august" => Month::August,
-        "september" => Month::September,
-        "october" => Month::October,
-        "november" => Month::November,
-        "december" => Month::December,
-        _ => return None,
-    } as u32;
+#[derive(Serialize)]
+pub struct CoverageResponse {
+    pub week_start: String,
+    pub summary: String,
+    pub periods: Vec<PeriodCoverage>,
+    pub missing: Vec<String>,
+}
 
-    NaiveDate::from_ymd_opt(year, month, day)
+#[derive(Deserialize)]
+pub struct PreviewRequest {
+    pub url: String,
 }
 
-fn choose_inferred_week_start(
-    week_starts: &[NaiveDate],
-    requested_date: NaiveDate,
-    today: NaiveDate,
-) -> Option<NaiveDate> {
-    if week_starts.is_empty() {
-        return None;
-    }
+#[derive(Serialize)]
+pub struct PreviewDiagnostics {
+    pub text_length: usize,
+    pub line_count: usize,
+    /// Where the week start came from: "pdf", "filename", or null if undetected.
+    pub week_source: Option<&'static str>,
+    pub weeks_in_pdf: usize,
+    pub coverage: Option<CoverageResponse>,
+    pub parse: Option<ParseDiagnostics>,
+}
 
-    // Prefer an exact containing week first.
-    for week_start in week_starts {
-        let week_end = *week_start + chrono::Duration::days(6);
-        if requested_date >= *week_start && requested_date <= week_end {
-            return Some(*week_start);
-        }
-    }
+#[derive(Serialize)]
+pub struct PreviewResponse {
+    pub url: String,
+    pub week_start: Option<String>,
+    pub menus: BTreeMap<String, String>,
+    #[serde(flatten)]
+    pub notes: WeekNotes,
+    pub diagnostics: PreviewDiagnostics,
+}
 
-    // Infer target menu week from the menu week that best represents "today".
-    let today_week = week_starts
-        .iter()
-        .min_by_key(|candidate| (today - **candidate).num_days().abs())
-        .copied()?;
+#[derive(Deserialize)]
+pub struct TermParams {
+    pub name: String,
+    pub year: Option<i32>,
+}
 
-    let delta_weeks = (requested_date - today).num_days().div_euclid(7);
-    let inferred_target = today_week + chrono::Duration::days(delta_weeks * 7);
+/// A named term, identified by the calendar year it starts in.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Term {
+    pub name: String,
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+}
 
-    week_starts
-        .iter()
-        .min_by_key(|candidate| (inferred_target - **candidate).num_days().abs())
-        .copied()
+#[derive(Serialize)]
+pub struct TermResponse {
+    pub term: String,
+    pub year: i32,
+    pub start: String,
+    pub end: String,
+    pub weeks: Vec<WeekResponse>,
 }
 
-pub fn parse_date_param(input: &str) -> Option<NaiveDate> {
-    let parts: Vec<_> = input
-        .split(|c| c == '-' || c == '/')
-        .filter(|s| !s.is_empty())
-        .collect();
-    if parts.len() != 3 {
-        return None;
-    }
-    let year = parts[0].parse::<i32>().ok()?;
-    let month = parts[1].parse::<u32>().ok()?;
-    let day = parts[2].parse::<u32>().ok()?;
-    NaiveDate::from_ymd_opt(year, month, day)
+#[derive(Deserialize)]
+pub struct SearchParams {
+    pub q: String,
+    #[serde(default)]
+    pub fuzzy: bool,
 }
 
-fn format_date(date: NaiveDate) -> String {
-    format!("{:04}-{:02}-{:02}", date.year(), date.month(), date.day())
+#[derive(Serialize)]
+pub struct SearchResult {
+    pub date: String,
+    pub period: String,
+    pub menu_type: String,
+    pub meal: String,
+    pub score: f64,
 }
 
-pub async fn download_and_extract_text(client: &Client, url: &str) -> anyhow::Result<String> {
-    let bytes = client.get(url).send().await?.bytes().await?;
-    let text = pdf_extract::extract_text_from_mem(&bytes)?;
-    Ok(text)
+/// Monday to Friday, the days lunch and breakfast are served.
+pub const WEEKDAYS: [Weekday; 5] = [
+    Weekday::Mon,
+    Weekday::Tue,
+    Weekday::Wed,
+    Weekday::Thu,
+    Weekday::Fri,
+];
+/// Saturday and Sunday, the days brunch replaces breakfast and lunch.
+pub const WEEKEND: [Weekday; 2] = [Weekday::Sat, Weekday::Sun];
+/// Every day of the week, Monday first; dinner is served on all of them.
+pub const ALL_DAYS: [Weekday; 7] = [
+    Weekday::Mon,
+    Weekday::Tue,
+    Weekday::Wed,
+    Weekday::Thu,
+    Weekday::Fri,
+    Weekday::Sat,
+    Weekday::Sun,
+];
+
+/// Expected period slots per week as (period, days served).
+pub const EXPECTED_SLOTS: [(MealPeriod, &[Weekday]); 4] = [
+    (MealPeriod::Breakfast, &WEEKDAYS),
+    (MealPeriod::Brunch, &WEEKEND),
+    (MealPeriod::Lunch, &WEEKDAYS),
+    (MealPeriod::Dinner, &ALL_DAYS),
+];
+
+/// The weekday menu weeks start on, from `week_start_day` (default Monday).
+pub fn week_start_day() -> Weekday {
+    static DAY: std::sync::OnceLock<Weekday> = std::sync::OnceLock::new();
+    *DAY.get_or_init(|| {
+        config::config()
+            .week_start_day
+            .trim()
+            .parse::<Weekday>()
+            .unwrap_or(Weekday::Mon)
+    })
 }
 
-pub fn is_junk_line(trimmed: &str, lower: &str) -> bool {
-    if trimmed.is_empty() {
-        return true;
-    }
-    if trimmed.chars().all(|c| !c.is_alphanumeric()) {
-        return true;
-    }
-    if trimmed == "\"" {
-        return true;
+/// Days from the start of the week to `weekday`, for weeks starting on `first_day`.
+pub fn weekday_offset(weekday: Weekday, first_day: Weekday) -> i64 {
+    ((weekday.num_days_from_monday() + 7 - first_day.num_days_from_monday()) % 7) as i64
+}
+
+pub fn weekday_date(week_start: NaiveDate, weekday: Weekday, first_day: Weekday) -> NaiveDate {
+    week_start + chrono::Duration::days(weekday_offset(weekday, first_day))
+}
+
+/// Today's date in the configured `timezone`.
+pub fn today() -> NaiveDate {
+    today_in(&config::config().timezone, chrono::Utc::now())
+}
+
+/// The date at `now` in `timezone`: `Europe/London`, `local`, a fixed offset
+/// such as `+01:00`, or `UTC`, which is also used for anything unrecognised.
+pub fn today_in(timezone: &str, now: chrono::DateTime<chrono::Utc>) -> NaiveDate {
+    let timezone = timezone.trim();
+    if timezone.eq_ignore_ascii_case("local") {
+        return now.with_timezone(&Local).date_naive();
     }
-    if lower.contains("cranbrook") || lower.contains("menu") || lower.contains("week commencing") {
-        return true;
+    let offset = if timezone.eq_ignore_ascii_case("europe/london") {
+        london_offset(now)
+    } else {
+        timezone
+            .parse::<chrono::FixedOffset>()
+            .unwrap_or(chrono::Offset::fix(&chrono::Utc))
+    };
+    now.with_timezone(&offset).date_naive()
+}
+
+/// GMT, or BST from 01:00 UTC on the last Sunday of March until 01:00 UTC on
+/// the last Sunday of October.
+fn london_offset(now: chrono::DateTime<chrono::Utc>) -> chrono::FixedOffset {
+    let change = |month: u32| {
+        let last_day = NaiveDate::from_ymd_opt(now.year(), month + 1, 1)
+            .and_then(|first| first.pred_opt())
+            .expect("March and October are valid months");
+        let sunday =
+            last_day - chrono::Duration::days(last_day.weekday().num_days_from_sunday().into());
+        sunday
+            .and_hms_opt(1, 0, 0)
+            .expect("01:00 is a valid time")
+            .and_utc()
+    };
+    let summer = now >= change(3) && now < change(10);
+    chrono::FixedOffset::east_opt(if summer { 3600 } else { 0 }).expect("offset within a day")
+}
+
+pub const MAX_DATE_PARAM_LEN: usize = 32;
+/// Long enough for every period in one comma-separated list.
+pub const MAX_PERIOD_PARAM_LEN: usize = 64;
+
+/// Rejects a parameter longer than `max_len` characters, then strips control
+/// characters, so pathological input never reaches the parsers.
+pub fn sanitize_param(name: &str, value: &str, max_len: usize) -> Result<String, String> {
+    if value.chars().nth(max_len).is_some() {
+        return Err(format!(
+            "The '{name}' parameter is too long (max {max_len} characters)."
+        ));
     }
-    false
+    Ok(value.chars().filter(|c| !c.is_control()).collect())
 }
 
-pub fn split_blocks(lines: &[String], expected_blocks: usize) -> Vec<Vec<String>> {
-    let mut blocks: Vec<Vec<String>> = Vec::new();
-    for raw in lines {
-        let trimmed = raw.trim();
-        let lower = trimmed.to_lowercase();
-        if is_junk_line(trimmed, &lower) {
-            continue;
-        }
-        let starts_new =
-            raw.starts_with(' ') && !blocks.is_empty() && blocks.len() < expected_blocks;
-        if starts_new {
-            blocks.push(Vec::new());
-        }
-        if blocks.is_empty() {
-            blocks.push(Vec::new());
-        }
-        blocks.last_mut().unwrap().push(trimmed.to_string());
+/// Longest `/search` query.
+pub const MAX_SEARCH_PARAM_LEN: usize = 128;
+/// Limit for parameters without one of their own, e.g. a term name.
+pub const MAX_PARAM_LEN: usize = 64;
+
+/// The limit `sanitize_param` applies to the parameter `name`.
+pub fn param_max_len(name: &str) -> usize {
+    match name {
+        "date" => MAX_DATE_PARAM_LEN,
+        "period" => MAX_PERIOD_PARAM_LEN,
+        "q" => MAX_SEARCH_PARAM_LEN,
+        _ => MAX_PARAM_LEN,
     }
-    blocks
 }
 
-pub fn fill_first_line_per_day(
-    lines: &[String],
-    week_start: NaiveDate,
-    days: usize,
-    period: &str,
-    out: &mut HashMap<String, String>,
-) {
-    let mut found = vec![false; days];
-    for raw in lines {
-        let trimmed = raw.trim();
-        let lower = trimmed.to_lowercase();
-        if is_junk_line(trimmed, &lower) {
-            continue;
-        }
-        for day in 0..days {
-            if !found[day] {
-                let date = week_start + chrono::Duration::days(day as i64);
-                let key = format!("{}-{period}", format_date(date));
-                out.insert(key, trimmed.to_string());
-                found[day] = true;
-                break;
-            }
+/// Runs every parameter of `uri`'s query through `sanitize_param`, then
+/// deserializes the result like `Query`.
+fn parse_sanitized_query<T: serde::de::DeserializeOwned>(
+    uri: &axum::http::Uri,
+) -> Result<T, String> {
+    let mut sanitized = Url::parse("http://localhost/").expect("valid base URL");
+    if let Some(query) = uri.query() {
+        let original = Url::parse(&format!("http://localhost/?{query}"))
+            .map_err(|err| format!("Invalid query string: {err}"))?;
+        let mut pairs = sanitized.query_pairs_mut();
+        for (name, value) in original.query_pairs() {
+            let value = sanitize_param(&name, &value, param_max_len(&name))?;
+            pairs.append_pair(&name, &value);
         }
     }
+    let uri = format!("/?{}", sanitized.query().unwrap_or_default())
+        .parse::<axum::http::Uri>()
+        .map_err(|err| format!("Invalid query string: {err}"))?;
+    Query::<T>::try_from_uri(&uri)
+        .map(|Query(value)| value)
+        .map_err(|err| err.body_text())
 }
 
-pub fn parse_weekly_menu(text: &str, week_start: NaiveDate) -> HashMap<String, String> {
-    let mut out = HashMap::new();
-    let lines: Vec<_> = text.lines().collect();
+/// `Query` with every parameter sanitized first, so all endpoints share the
+/// same length limits and control-character stripping.
+pub struct SanitizedQuery<T>(pub T);
 
-    // The PDF has a table structure where days are columns
-    // We need to track which section (breakfast/brunch/lunch/dinner) we're in
-    // and parse the first non-empty item for each day in that section
+impl<T: serde::de::DeserializeOwned, S: Send + Sync> FromRequestParts<S> for SanitizedQuery<T> {
+    type Rejection = (StatusCode, String);
 
-    let mut in_breakfast = false;
-    let mut in_brunch_sat = false;
-    let mut in_brunch_sun = false;
-    let mut in_lunch = false;
-    let mut in_dinner = false;
+    async fn from_request_parts(
+        parts: &mut axum::http::request::Parts,
+        _state: &S,
+    ) -> Result<Self, Self::Rejection> {
+        parse_sanitized_query(&parts.uri)
+            .map(SanitizedQuery)
+            .map_err(|message| (StatusCode::BAD_REQUEST, message))
+    }
+}
 
-    let mut breakfast_found = [false; 5]; // Mon-Fri
-    let mut brunch_sat_found = false;
-    let mut brunch_sun_found = false;
-    let mut lunch_lines: Vec<String> = Vec::new();
-    let mut dinner_lines: Vec<String> = Vec::new();
+/// `/meal` query parameters after validation.
+#[derive(Debug, PartialEq)]
+pub struct MealQuery {
+    pub date: NaiveDate,
+    pub periods: Vec<MealPeriod>,
+    pub explain: bool,
+    pub fallback_prev: bool,
+}
 
-    for line in lines {
-        let trimmed = line.trim();
-        let lower = trimmed.to_lowercase();
+#[derive(Debug, PartialEq, Serialize)]
+pub struct FieldError {
+    pub field: &'static str,
+    pub message: String,
+}
 
-        // Detect section headers - look for lines with multiple instances of the period name
-        let breakfast_count = lower.matches("breakfast").count();
-        let brunch_count = lower.matches("brunch").count();
-        let lunch_count = lower.matches("lunch").count();
-        let dinner_count = lower.matches("dinner").count();
+/// Every invalid field of a `/meal` query, answered as a 400.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct MealQueryRejection {
+    pub errors: Vec<FieldError>,
+}
 
-        if breakfast_count >= 3 {
-            in_breakfast = true;
-            in_brunch_sat = false;
-            in_brunch_sun = false;
-            in_lunch = false;
-            in_dinner = false;
-            continue;
-        }
-        if brunch_count >= 1 && !in_brunch_sat {
-            in_breakfast = false;
-            in_brunch_sat = true;
-            in_brunch_sun = false;
-            in_lunch = false;
-            in_dinner = false;
-            continue;
-        }
-        if brunch_count >= 1 && in_brunch_sat {
-            in_brunch_sat = false;
-            in_brunch_sun = true;
-            continue;
-        }
-        if lunch_count >= 3 {
-            in_breakfast = false;
-            in_brunch_sat = false;
-            in_brunch_sun = false;
-            in_lunch = true;
-            in_dinner = false;
-            continue;
-        }
-        if dinner_count >= 3 {
-            in_breakfast = false;
-            in_brunch_sat = false;
-            in_brunch_sun = false;
-            in_lunch = false;
-            in_dinner = true;
-            continue;
+impl MealQueryRejection {
+    fn single(field: &'static str, message: impl Into<String>) -> Self {
+        MealQueryRejection {
+            errors: vec![FieldError {
+                field,
+                message: message.into(),
+            }],
         }
+    }
+}
 
-        if in_lunch {
-            lunch_lines.push(line.to_string());
-        } else if in_dinner {
-            dinner_lines.push(line.to_string());
-        }
+impl IntoResponse for MealQueryRejection {
+    fn into_response(self) -> axum::response::Response {
+        (
+            StatusCode::BAD_REQUEST,
+            axum::Json(serde_json::json!({
+                "error": "Invalid query parameters",
+                "errors": self.errors,
+            })),
+        )
+            .into_response()
+    }
+}
 
-        // Skip empty lines and lines with common filler text
-        if is_junk_line(trimmed, &lower) {
-            continue;
-        }
+impl TryFrom<QueryParams> for MealQuery {
+    type Error = MealQueryRejection;
 
-        // Extract first meal for each day in current section
-        if in_breakfast {
-            for day in 0..5 {
-                if !breakfast_found[day] && !trimmed.is_empty() {
-                    let date = week_start + chrono::Duration::days(day as i64);
-                    let key = format!("{}-breakfast", format_date(date));
-                    out.insert(key, trimmed.to_string());
-                    breakfast_found[day] = true;
-                    break;
-                }
+    /// Validates every field, reporting all the invalid ones rather than the first.
+    fn try_from(params: QueryParams) -> Result<Self, Self::Error> {
+        let mut errors = Vec::new();
+        let mut reject = |field, message: String| {
+            errors.push(FieldError { field, message });
+        };
+
+        let date = sanitize_param("date", &params.date, MAX_DATE_PARAM_LEN)
+            .and_then(|date| try_parse_date_param(&date).map_err(|err| err.to_string()))
+            .map_err(|message| reject("date", message))
+            .ok();
+        let periods = sanitize_param("period", &params.period, MAX_PERIOD_PARAM_LEN)
+            .and_then(|period| {
+                parse_periods(&period).map_err(|unknown| {
+                    format!(
+                        "Unrecognized period(s): {}. Use breakfast, break, brunch, lunch or dinner.",
+                        unknown.join(", ")
+                    )
+                })
+            })
+            .map_err(|message| reject("period", message))
+            .ok();
+        let fallback_prev = match params.fallback.as_deref().map(str::trim) {
+            None | Some("") => false,
+            Some(value) if value.eq_ignore_ascii_case("prev") => true,
+            Some(_) => {
+                reject(
+                    "fallback",
+                    "Unrecognized fallback. Use fallback=prev.".to_string(),
+                );
+                false
             }
-        } else if in_brunch_sat && !brunch_sat_found {
-            let date = week_start + chrono::Duration::days(5); // Saturday
-            let key = format!("{}-brunch", format_date(date));
-            out.insert(key, "Brunch buffet available".to_string());
-            brunch_sat_found = true;
-        } else if in_brunch_sun && !brunch_sun_found {
-            let date = week_start + chrono::Duration::days(6); // Sunday
-            let key = format!("{}-brunch", format_date(date));
-            out.insert(key, "Brunch buffet available".to_string());
-            brunch_sun_found = true;
+        };
+
+        match (date, periods) {
+            (Some(date), Some(periods)) if errors.is_empty() => Ok(MealQuery {
+                date,
+                periods,
+                explain: params.explain,
+                fallback_prev,
+            }),
+            _ => Err(MealQueryRejection { errors }),
         }
     }
+}
 
-    let lunch_blocks = split_blocks(&lunch_lines, 5);
-    if lunch_blocks.len() == 5 {
-        for day in 0..5 {
-            if let Some(block) = lunch_blocks.get(day) {
-                let date = week_start + chrono::Duration::days(day as i64);
-                let key = format!("{}-lunch", format_date(date));
-                out.insert(key, block.join("\n"));
+impl<S: Send + Sync> FromRequestParts<S> for MealQuery {
+    type Rejection = MealQueryRejection;
+
+    async fn from_request_parts(
+        parts: &mut axum::http::request::Parts,
+        _state: &S,
+    ) -> Result<Self, Self::Rejection> {
+        let params = parse_sanitized_query::<QueryParams>(&parts.uri)
+            .map_err(|message| MealQueryRejection::single("query", message))?;
+        MealQuery::try_from(params)
+    }
+}
+
+pub async fn get_meal(query: MealQuery) -> impl IntoResponse {
+    let MealQuery {
+        date,
+        periods,
+        explain,
+        fallback_prev,
+    } = query;
+    if periods.len() > 1 {
+        return multi_period_response(date, &periods).await;
+    }
+    let period = periods[0].as_str().to_string();
+
+    if let Some(message) = not_served_reason(&period, date) {
+        return (
+            StatusCode::NOT_FOUND,
+            axum::Json(serde_json::json!({
+                "status": "not_served",
+                "date": format_output_date(date),
+                "period": period,
+                "message": message,
+            })),
+        )
+            .into_response();
+    }
+
+    if explain {
+        return match explain_meal_for_date(date, &period).await {
+            Ok(Some(explanation)) => json_with_etag(&explanation),
+            Ok(None) => week_not_found(date),
+            Err(err) => fetch_error_response(date, err),
+        };
+    }
+
+    let week = fetch_week_for_date(date).await;
+    if matches!(week, Ok(None)) && no_menus_found() {
+        return no_menus_response();
+    }
+    let fetched = match week {
+        Ok(Some(week)) => match lookup_meal(&week.menus, week.week_start, date, &period) {
+            Some(meal) => Ok(Some((meal, week))),
+            None if fallback_prev => previous_week_meal(&week, date, &period).await,
+            None => Ok(None),
+        },
+        Ok(None) => Ok(None),
+        Err(err) => Err(err),
+    };
+
+    match fetched {
+        Ok(Some((meal, week))) => mark_stale(
+            json_with_etag(&MealResponse {
+                date: format_output_date(date),
+                period,
+                meal,
+                resolution: week.resolution.as_str(),
+                week_start: match week.resolution {
+                    WeekResolution::Exact(_) => None,
+                    WeekResolution::Inferred(week_start)
+                    | WeekResolution::PreviousWeekFallback(week_start) => {
+                        Some(format_output_date(week_start))
+                    }
+                },
+            }),
+            week.stale,
+        ),
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            format!("Meal not found for {} {}", format_date(date), period),
+        )
+            .into_response(),
+        Err(err) => fetch_error_response(date, err),
+    }
+}
+
+async fn multi_period_response(
+    date: NaiveDate,
+    periods: &[MealPeriod],
+) -> axum::response::Response {
+    let week = match fetch_week_for_date(date).await {
+        Ok(None) if no_menus_found() => return no_menus_response(),
+        Ok(week) => week,
+        Err(err) => return fetch_error_response(date, err),
+    };
+
+    let mut meals = BTreeMap::new();
+    for period in periods {
+        let meal = match &week {
+            Some(week) if not_served_reason(period.as_str(), date).is_none() => {
+                lookup_meal(&week.menus, week.week_start, date, period.as_str())
             }
+            _ => None,
+        };
+        meals.insert(period.as_str().to_string(), meal);
+    }
+
+    let stale = week.as_ref().is_some_and(|week| week.stale);
+    mark_stale(
+        json_with_etag(&MultiMealResponse {
+            date: format_output_date(date),
+            meals,
+        }),
+        stale,
+    )
+}
+
+pub const DEFAULT_PUBLISH_LOOKAHEAD_DAYS: i64 = 14;
+
+/// Days past the latest published week still treated as "not published yet".
+pub fn publish_lookahead_days() -> i64 {
+    config::config().publish_lookahead_days
+}
+
+/// True when `date` falls just after the latest published week, within the look-ahead.
+pub fn is_pending_publication(
+    published: &[NaiveDate],
+    date: NaiveDate,
+    lookahead_days: i64,
+) -> bool {
+    let Some(latest) = published.iter().max() else {
+        return false;
+    };
+    let latest_end = *latest + chrono::Duration::days(6);
+    date > latest_end && date <= latest_end + chrono::Duration::days(lookahead_days)
+}
+
+/// Fails with `NotYetPublished` rather than inferring a week for a date just
+/// past the latest published one.
+pub fn ensure_published(
+    menus: &[(String, NaiveDate)],
+    date: NaiveDate,
+) -> Result<(), CateringError> {
+    let published: Vec<NaiveDate> = menus.iter().map(|(_, week_start)| *week_start).collect();
+    if is_pending_publication(&published, date, publish_lookahead_days()) {
+        return Err(CateringError::NotYetPublished { date });
+    }
+    Ok(())
+}
+
+pub fn is_not_yet_published(err: &anyhow::Error) -> bool {
+    matches!(
+        err.downcast_ref::<CateringError>(),
+        Some(CateringError::NotYetPublished { .. })
+    )
+}
+
+/// The response for a failed week lookup: "pending" for unpublished weeks,
+/// otherwise a 502.
+fn fetch_error_response(date: NaiveDate, err: anyhow::Error) -> axum::response::Response {
+    if is_not_yet_published(&err) {
+        return pending_response(date);
+    }
+    (
+        StatusCode::BAD_GATEWAY,
+        format!("Failed to fetch menu data: {err}"),
+    )
+        .into_response()
+}
+
+fn pending_response(date: NaiveDate) -> axum::response::Response {
+    axum::Json(serde_json::json!({
+        "status": "pending",
+        "date": format_output_date(date),
+        "message": "Menu for this week hasn't been published yet",
+    }))
+    .into_response()
+}
+
+/// Flags responses built from stale cache data with `X-Data-Stale: true`.
+pub fn mark_stale(mut response: axum::response::Response, stale: bool) -> axum::response::Response {
+    if stale {
+        response.headers_mut().insert(
+            header::HeaderName::from_static("x-data-stale"),
+            HeaderValue::from_static("true"),
+        );
+    }
+    response
+}
+
+/// Looks up many (date, period) pairs in one request, answering in input order.
+/// The catering page is resolved once and each week loaded at most once.
+pub async fn post_meals(
+    State(config): State<Arc<config::Config>>,
+    axum::Json(items): axum::Json<Vec<BatchItem>>,
+) -> impl IntoResponse {
+    let max = config.max_batch_size;
+    if items.len() > max {
+        return (
+            StatusCode::BAD_REQUEST,
+            format!("Batch too large: {} items (max {max}).", items.len()),
+        )
+            .into_response();
+    }
+    if items.is_empty() {
+        return axum::Json(Vec::<BatchResult>::new()).into_response();
+    }
+
+    let resolved = match build_client() {
+        Ok(client) => resolve_menu_weeks(&client)
+            .await
+            .map(|menus| (client, menus)),
+        Err(err) => Err(err),
+    };
+    let (client, menus) = match resolved {
+        Ok(value) => value,
+        Err(err) => {
+            return (
+                StatusCode::BAD_GATEWAY,
+                format!("Failed to fetch menu data: {err}"),
+            )
+                .into_response();
         }
-    } else {
-        fill_first_line_per_day(&lunch_lines, week_start, 5, "lunch", &mut out);
+    };
+    if menus.is_empty() && no_menus_found() {
+        return no_menus_response();
     }
 
-    let dinner_blocks = split_blocks(&dinner_lines, 7);
-    if dinner_blocks.len() == 7 {
-        for day in 0..7 {
-            if let Some(block) = dinner_blocks.get(day) {
-                let date = week_start + chrono::Duration::days(day as i64);
-                let key = format!("{}-dinner", format_date(date));
-                out.insert(key, block.join("\n"));
+    let today = today();
+    let mut weeks: HashMap<NaiveDate, Result<CachedWeek, String>> = HashMap::new();
+    let mut results = Vec::with_capacity(items.len());
+    for item in items {
+        let mut result = BatchResult {
+            date: item.date.clone(),
+            period: item.period.clone(),
+            meal: None,
+            error: None,
+        };
+        let sanitized = [("date", &item.date), ("period", &item.period)]
+            .map(|(name, value)| sanitize_param(name, value, param_max_len(name)));
+        let [date_raw, period_raw] = match sanitized {
+            [Ok(date), Ok(period)] => [date, period],
+            [Err(message), _] | [_, Err(message)] => {
+                result.error = Some(message);
+                results.push(result);
+                continue;
             }
+        };
+        let date = match try_parse_date_param(&date_raw) {
+            Ok(date) => date,
+            Err(err) => {
+                result.error = Some(err.to_string());
+                results.push(result);
+                continue;
+            }
+        };
+        let Ok(period) = period_raw.trim().parse::<MealPeriod>() else {
+            result.error = Some(format!("Unrecognized period: {}", item.period));
+            results.push(result);
+            continue;
+        };
+        result.date = format_output_date(date);
+        result.period = period.as_str().to_string();
+
+        if let Some(message) = not_served_reason(period.as_str(), date) {
+            result.error = Some(message.to_string());
+            results.push(result);
+            continue;
         }
-    } else {
-        fill_first_line_per_day(&dinner_lines, week_start, 7, "dinner", &mut out);
+        if let Err(err) = ensure_published(&menus, date) {
+            result.error = Some(err.to_string());
+            results.push(result);
+            continue;
+        }
+        let Some((link, resolution)) = resolve_target(&menus, date, today) else {
+            result.error = Some(format!("No menu week found for {}", format_date(date)));
+            results.push(result);
+            continue;
+        };
+
+        let week_start = resolution.week_start();
+        if !weeks.contains_key(&week_start) {
+            let week = load_week(&client, &link, week_start)
+                .await
+                .map_err(|err| format!("Failed to fetch menu data: {err}"));
+            weeks.insert(week_start, week);
+        }
+        match &weeks[&week_start] {
+            Ok(week) => result.meal = lookup_meal(&week.menus, week_start, date, period.as_str()),
+            Err(err) => result.error = Some(err.clone()),
+        }
+        results.push(result);
     }
 
-    out
+    axum::Json(results).into_response()
 }
 
-pub async fn build_index() -> anyhow::Result<HashMap<String, String>> {
-    let client = build_client()?;
-    let links = fetch_menu_links(&client).await?;
+/// Serializes `value` as JSON with an `ETag` derived from the body, so GET and
+/// HEAD expose the same validator.
+pub fn json_with_etag<T: Serialize>(value: &T) -> axum::response::Response {
+    let body = match serde_json::to_vec(value) {
+        Ok(body) => body,
+        Err(err) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to serialize response: {err}"),
+            )
+                .into_response();
+        }
+    };
 
-    let mut index = HashMap::new();
-    for (link, week_start_opt) in links {
-        println!("Processing {link}");
-        let text = download_and_extract_text(&client, &link).await?;
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    std::hash::Hash::hash(&body, &mut hasher);
+    let etag = format!("\"{:016x}\"", std::hash::Hasher::finish(&hasher));
+
+    (
+        [
+            (header::CONTENT_TYPE, "application/json"),
+            (header::ETAG, etag.as_str()),
+        ],
+        body,
+    )
+        .into_response()
+}
 
-        if let Some(week_start) = week_start_opt {
-            println!("Week starting: {}", week_start);
-            let week_menus = parse_weekly_menu(&text, week_start);
+/// Parses a `date` query parameter, answering 400 with which part was wrong.
+pub fn parse_date_query(raw: &str) -> Result<NaiveDate, axum::response::Response> {
+    try_parse_date_param(raw)
+        .map_err(|err| (StatusCode::BAD_REQUEST, err.to_string()).into_response())
+}
+
+pub async fn get_week(SanitizedQuery(params): SanitizedQuery<WeekParams>) -> impl IntoResponse {
+    let date = match parse_date_query(&params.date) {
+        Ok(date) => date,
+        Err(response) => return response,
+    };
 
-            for (k, v) in week_menus {
-                println!("Storing key: {} -> {}", k, v);
-                index.insert(k, v);
+    match fetch_week_for_date(date).await {
+        Ok(Some(week)) => {
+            let mut response = week_response(&week);
+            if params.nav {
+                let (prev_week, next_week) = week_neighbors(week.week_start, &week.published);
+                response.prev_week = Some(prev_week);
+                response.next_week = Some(next_week);
             }
-        } else {
-            println!("Skipping - could not parse week start date");
+            mark_stale(axum::Json(response).into_response(), week.stale)
         }
+        Ok(None) => week_not_found(date),
+        Err(err) => fetch_error_response(date, err),
     }
+}
 
-    println!("\nTotal entries in index: {}", index.len());
-    println!(
-        "Sample keys: {:?}",
-        index.keys().take(5).collect::<Vec<_>>()
-    );
+pub async fn get_day(SanitizedQuery(params): SanitizedQuery<DateParams>) -> impl IntoResponse {
+    let date = match parse_date_query(&params.date) {
+        Ok(date) => date,
+        Err(response) => return response,
+    };
 
-    Ok(index)
+    match fetch_week_for_date(date).await {
+        Ok(Some(week)) => mark_stale(
+            axum::Json(day_response(&week, date)).into_response(),
+            week.stale,
+        ),
+        Ok(None) => week_not_found(date),
+        Err(err) => fetch_error_response(date, err),
+    }
 }
 
-pub async fn fetch_meal_for_date(date: NaiveDate, period: &str) -> anyhow::Result<Option<String>> {
-    let client = build_client()?;
-    let links = fetch_menu_links(&client).await?;
+/// Lays a week out like the physical menu board: weekday names as keys.
+pub fn week_grid(week: &WeekResponse) -> WeekGrid {
+    WeekGrid(
+        week.days
+            .iter()
+            .map(|day| {
+                let meals = day
+                    .meals
+                    .iter()
+                    .map(|meal| (meal.period.clone(), meal.items.clone()))
+                    .collect();
+                (
+                    day.weekday.clone(),
+                    GridDay {
+                        date: day.date.clone(),
+                        meals,
+                    },
+                )
+            })
+            .collect(),
+    )
+}
 
-    let mut menus: Vec<(String, NaiveDate, Option<String>)> = Vec::new();
+pub async fn get_week_grid(
+    SanitizedQuery(params): SanitizedQuery<DateParams>,
+) -> impl IntoResponse {
+    let date = match parse_date_query(&params.date) {
+        Ok(date) => date,
+        Err(response) => return response,
+    };
 
-    for (link, week_start_opt) in links {
-        if let Some(week_start) = week_start_opt {
-            menus.push((link, week_start, None));
-            continue;
-        }
+    match fetch_week_for_date(date).await {
+        Ok(Some(week)) => mark_stale(
+            axum::Json(week_grid(&week_response(&week))).into_response(),
+            week.stale,
+        ),
+        Ok(None) => week_not_found(date),
+        Err(err) => fetch_error_response(date, err),
+    }
+}
 
-        // If week start is not available in anchor text, inspect the PDF content.
-        let text = match download_and_extract_text(&client, &link).await {
-            Ok(value) => value,
-            Err(_) => continue,
-        };
-        if let Some(week_start) = parse_week_commencing_from_pdf_text(&text) {
-            menus.push((link, week_start, Some(text)));
-        }
+pub async fn get_week_markdown(
+    SanitizedQuery(params): SanitizedQuery<DateParams>,
+) -> impl IntoResponse {
+    let date = match parse_date_query(&params.date) {
+        Ok(date) => date,
+        Err(response) => return response,
+    };
+
+    match fetch_week_for_date(date).await {
+        Ok(Some(week)) => mark_stale(
+            (
+                [(header::CONTENT_TYPE, "text/markdown; charset=utf-8")],
+                render_week_markdown(&week_response(&week)),
+            )
+                .into_response(),
+            week.stale,
+        ),
+        Ok(None) => week_not_found(date),
+        Err(err) => fetch_error_response(date, err),
     }
+}
 
-    let week_starts: Vec<NaiveDate> = menus.iter().map(|(_, week_start, _)| *week_start).collect();
-    let today = Local::now().date_naive();
-    let target_week_start = match choose_inferred_week_start(&week_starts, date, today) {
-        Some(value) => value,
-        None => return Ok(None),
+pub async fn get_coverage(SanitizedQuery(params): SanitizedQuery<DateParams>) -> impl IntoResponse {
+    let date = match parse_date_query(&params.date) {
+        Ok(date) => date,
+        Err(response) => return response,
     };
 
-    let mut matched: Option<(String, Option<String>)> = None;
-    for (link, week_start, cached_text) in menus {
-        if week_start == target_week_start {
-            matched = Some((link, cached_text));
-            break;
-        }
+    match fetch_week_for_date(date).await {
+        Ok(Some(week)) => mark_stale(
+            axum::Json(week_coverage(&week.menus, week.week_start)).into_response(),
+            week.stale,
+        ),
+        Ok(None) => week_not_found(date),
+        Err(err) => fetch_error_response(date, err),
     }
-    let (link, cached_text) = match matched {
-        Some(value) => value,
-        None => return Ok(None),
+}
+
+pub async fn get_search(SanitizedQuery(params): SanitizedQuery<SearchParams>) -> impl IntoResponse {
+    let query = params.q.trim();
+    if query.is_empty() {
+        return (StatusCode::BAD_REQUEST, "Missing search query 'q'.").into_response();
+    }
+
+    let index = cached_index().await;
+    if no_menus_found() {
+        return no_menus_response();
+    }
+    axum::Json(search_index(&index, query, params.fuzzy)).into_response()
+}
+
+/// The menus of every week already in the cache. On a cold start the current
+/// and next weeks are loaded first; weeks that fail to load are skipped.
+async fn cached_index() -> HashMap<String, String> {
+    let empty = lock_cache().week_count() == 0;
+    if empty && let Err(err) = preload_current_weeks().await {
+        tracing::warn!("Could not warm the cache for search: {err}");
+    }
+    lock_cache().cached_menus()
+}
+
+/// Term dates used when `TERM_DATES` is unset: the 2025-26 and 2026-27 academic years.
+pub const DEFAULT_TERM_DATES: &str = "michaelmas:2025-09-03:2025-12-12,\
+    lent:2026-01-06:2026-03-27,\
+    summer:2026-04-21:2026-07-04,\
+    michaelmas:2026-09-02:2026-12-11,\
+    lent:2027-01-05:2027-03-26,\
+    summer:2027-04-20:2027-07-03";
+
+/// Parses comma-separated `name:start:end` entries, e.g. `lent:2026-01-06:2026-03-27`.
+pub fn parse_term_dates(raw: &str) -> Result<Vec<Term>, String> {
+    raw.split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let parts: Vec<&str> = entry.split(':').map(str::trim).collect();
+            let [name, start, end] = parts.as_slice() else {
+                return Err(format!("Invalid term entry {entry:?}; use name:start:end"));
+            };
+            let start =
+                parse_date_param(start).ok_or(format!("Invalid start date in {entry:?}"))?;
+            let end = parse_date_param(end).ok_or(format!("Invalid end date in {entry:?}"))?;
+            if end < start {
+                return Err(format!("Term {entry:?} ends before it starts"));
+            }
+            Ok(Term {
+                name: name.to_lowercase(),
+                start,
+                end,
+            })
+        })
+        .collect()
+}
+
+/// The configured term dates, falling back to `DEFAULT_TERM_DATES` if invalid.
+pub fn term_dates(config: &config::Config) -> Vec<Term> {
+    parse_term_dates(&config.term_dates).unwrap_or_else(|err| {
+        tracing::warn!("Invalid TERM_DATES ({err}); using defaults");
+        parse_term_dates(DEFAULT_TERM_DATES).unwrap_or_default()
+    })
+}
+
+pub fn find_term<'a>(terms: &'a [Term], name: &str, year: i32) -> Option<&'a Term> {
+    terms
+        .iter()
+        .find(|term| term.name.eq_ignore_ascii_case(name) && term.start.year() == year)
+}
+
+/// First month of the academic year, so a term named without a year means the
+/// one in the school year containing today.
+pub const ACADEMIC_YEAR_START_MONTH: u32 = 9;
+
+/// The named term in the academic year (September to August) containing `today`.
+pub fn find_academic_term<'a>(terms: &'a [Term], name: &str, today: NaiveDate) -> Option<&'a Term> {
+    let start_year = if today.month() >= ACADEMIC_YEAR_START_MONTH {
+        today.year()
+    } else {
+        today.year() - 1
     };
+    let starts = NaiveDate::from_ymd_opt(start_year, ACADEMIC_YEAR_START_MONTH, 1)?;
+    let ends = NaiveDate::from_ymd_opt(start_year + 1, ACADEMIC_YEAR_START_MONTH, 1)?;
+    terms.iter().find(|term| {
+        term.name.eq_ignore_ascii_case(name) && term.start >= starts && term.start < ends
+    })
+}
 
-    let text = match cached_text {
-        Some(value) => value,
-        None => download_and_extract_text(&client, &link).await?,
+/// Returns every published week overlapping the named term.
+pub async fn get_term(
+    State(config): State<Arc<config::Config>>,
+    SanitizedQuery(params): SanitizedQuery<TermParams>,
+) -> impl IntoResponse {
+    let name = params.name.trim();
+    let terms = term_dates(&config);
+    let term = match params.year {
+        Some(year) => find_term(&terms, name, year),
+        None => find_academic_term(&terms, name, today_in(&config.timezone, chrono::Utc::now())),
     };
-    let week_menus = parse_weekly_menu(&text, target_week_start);
-    let period_key = period.to_lowercase();
-    let key = format!("{}-{}", format_date(date), period_key);
+    let Some(term) = term else {
+        let when = params
+            .year
+            .map_or_else(|| "this academic year".to_string(), |year| year.to_string());
+        return (
+            StatusCode::BAD_REQUEST,
+            format!("Unknown term {name:?} for {when}."),
+        )
+            .into_response();
+    };
+    let year = term.start.year();
+
+    let loaded = match build_client() {
+        Ok(client) => load_term_weeks(&client, term).await,
+        Err(err) => Err(err),
+    };
+    match loaded {
+        Ok(weeks) if weeks.is_empty() && no_menus_found() => no_menus_response(),
+        Ok(weeks) if weeks.is_empty() => (
+            StatusCode::NOT_FOUND,
+            format!("No menu weeks found for {} {year}", term.name),
+        )
+            .into_response(),
+        Ok(weeks) => axum::Json(TermResponse {
+            term: term.name.clone(),
+            year,
+            start: format_output_date(term.start),
+            end: format_output_date(term.end),
+            weeks,
+        })
+        .into_response(),
+        Err(err) => (
+            StatusCode::BAD_GATEWAY,
+            format!("Failed to fetch menu data: {err}"),
+        )
+            .into_response(),
+    }
+}
 
-    if let Some(meal) = week_menus.get(&key) {
-        return Ok(Some(meal.clone()));
+async fn load_term_weeks(client: &Client, term: &Term) -> anyhow::Result<Vec<WeekResponse>> {
+    let mut menus = resolve_menu_weeks(client).await?;
+    menus.retain(|(_, week_start)| {
+        *week_start <= term.end && *week_start + chrono::Duration::days(6) >= term.start
+    });
+    menus.sort_by_key(|(_, week_start)| *week_start);
+
+    let mut weeks = Vec::new();
+    for (link, week_start) in menus {
+        // One unreadable PDF shouldn't hide the rest of the term.
+        let week = match load_week(client, &link, week_start).await {
+            Ok(week) => week,
+            Err(err) => {
+                tracing::warn!(
+                    "Skipping week {} of {}: {err}",
+                    format_date(week_start),
+                    term.name
+                );
+                continue;
+            }
+        };
+        let mut response = structure_week(&week.menus, week_start);
+        response.notes = week.notes;
+        weeks.push(response);
     }
+    Ok(weeks)
+}
 
-    // If we inferred a nearby week, map by weekday within that inferred week.
-    let weekday_offset = date.weekday().num_days_from_monday() as i64;
-    let mapped_date = target_week_start + chrono::Duration::days(weekday_offset);
-    let mapped_key = format!("{}-{}", format_date(mapped_date), period_key);
-    Ok(week_menus.get(&mapped_key).cloned())
+/// Streams the whole index as one JSON object, a week at a time, so memory
+/// stays bounded by the largest week rather than the archive.
+pub async fn get_menu_json() -> impl IntoResponse {
+    let resolved = match build_client() {
+        Ok(client) => resolve_menu_weeks(&client)
+            .await
+            .map(|menus| (client, menus)),
+        Err(err) => Err(err),
+    };
+    let (client, mut menus) = match resolved {
+        Ok(value) => value,
+        Err(err) => {
+            return (
+                StatusCode::BAD_GATEWAY,
+                format!("Failed to fetch menu data: {err}"),
+            )
+                .into_response();
+        }
+    };
+    if menus.is_empty() && no_menus_found() {
+        return no_menus_response();
+    }
+    menus.sort_by_key(|(_, week_start)| *week_start);
+
+    let weeks = async_stream::stream! {
+        for (link, week_start) in menus {
+            yield load_week(&client, &link, week_start)
+                .await
+                .map(|week| week.menus)
+                .map_err(|err| err.context(format!("week {week_start} from {link}")));
+        }
+    };
+    (
+        [(header::CONTENT_TYPE, "application/json")],
+        axum::body::Body::from_stream(stream_index_json(weeks)),
+    )
+        .into_response()
 }
 
-pub fn parse_payload<T>(event: &LambdaEvent<Value>, key: &str) -> Option<T>
+/// Encodes a stream of weekly menu maps as the chunks of a single JSON object.
+/// A failed week is logged and skipped, since the 200 has already been sent.
+pub fn stream_index_json<S>(
+    weeks: S,
+) -> impl futures_core::Stream<Item = Result<axum::body::Bytes, std::io::Error>>
 where
-    T: for<'de> serde::Deserialize<'de>,
+    S: futures_core::Stream<Item = anyhow::Result<HashMap<String, String>>>,
 {
-    event
-        .payload
-        .get(key)
-        .or_else(|| {
-            event
-                .payload
-                .get("queryStringParameters")
+    async_stream::stream! {
+        // Nothing fails any more, so name the error type for inference.
+        yield Ok::<_, std::io::Error>(axum::body::Bytes::from_static(b"{"));
+        let mut first = true;
+        for await week in weeks {
+            let menus = match week {
+                Ok(menus) => menus,
+                Err(err) => {
+                    tracing::warn!("Skipping {err:#}");
+                    continue;
+                }
+            };
+            let mut entries: Vec<_> = menus.into_iter().collect();
+            entries.sort();
+
+            let mut chunk = String::new();
+            for (key, meal) in entries {
+                if !first {
+                    chunk.push(',');
+                }
+                first = false;
+                chunk.push_str(&Value::String(key).to_string());
+                chunk.push(':');
+                chunk.push_str(&Value::String(meal).to_string());
+            }
+            yield Ok(axum::body::Bytes::from(chunk));
+        }
+        yield Ok(axum::body::Bytes::from_static(b"}"));
+    }
+}
+
+/// 200 once menus can be served; 503 while the last scrape found no menu links.
+pub async fn get_ready() -> impl IntoResponse {
+    if no_menus_found() {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            axum::Json(serde_json::json!({
+                "status": "degraded",
+                "reason": "no_menus_found",
+            })),
+        )
+            .into_response();
+    }
+    axum::Json(serde_json::json!({ "status": "ready" })).into_response()
+}
+
+pub const NO_MENUS_MESSAGE: &str = "No menus are currently listed on the catering page.";
+
+/// Answers a lookup when the catering pages list no menus at all, so clients
+/// see the cause rather than a per-date 404.
+pub fn no_menus_response() -> axum::response::Response {
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        axum::Json(serde_json::json!({
+            "status": "no_menus_found",
+            "message": NO_MENUS_MESSAGE,
+        })),
+    )
+        .into_response()
+}
+
+/// True when the last catering page scrape listed no menus at all.
+pub fn no_menus_found() -> bool {
+    lock_cache().no_menus_found()
+}
+
+/// 404 for a date no menu week covers, or `no_menus_response` when there
+/// are no menus at all.
+pub fn week_not_found(date: NaiveDate) -> axum::response::Response {
+    if no_menus_found() {
+        return no_menus_response();
+    }
+    (
+        StatusCode::NOT_FOUND,
+        format!("No menu week found for {}", format_date(date)),
+    )
+        .into_response()
+}
+
+pub async fn get_summary() -> impl IntoResponse {
+    axum::Json(lock_cache().summary(today()))
+}
+
+/// Default filter when `RUST_LOG` is unset: `info` for this crate and its
+/// binaries, `warn` for dependencies.
+pub const DEFAULT_LOG_FILTER: &str =
+    "warn,cranbrook_catering_api=info,main=info,serve=info,build_index=info";
+
+/// Installs a stdout subscriber filtered by `RUST_LOG`, e.g.
+/// `RUST_LOG=cranbrook_catering_api=debug` also logs every index key as it is
+/// stored. Safe to call more than once.
+pub fn init_tracing() {
+    let filter = tracing_subscriber::EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new(DEFAULT_LOG_FILTER));
+    let _ = tracing_subscriber::fmt().with_env_filter(filter).try_init();
+}
+
+pub fn build_router(config: Arc<config::Config>, cors: CorsLayer) -> Router {
+    let max_body_bytes = config.max_body_bytes;
+    Router::new()
+        .route("/meal", get(get_meal))
+        .route("/meals", axum::routing::post(post_meals))
+        .route("/coverage", get(get_coverage))
+        .route("/day", get(get_day))
+        .route("/week", get(get_week))
+        .route("/week.md", get(get_week_markdown))
+        .route("/week/grid", get(get_week_grid))
+        .route("/search", get(get_search))
+        .route("/summary", get(get_summary))
+        .route("/ready", get(get_ready))
+        .route("/menu.json", get(get_menu_json))
+        .route("/term", get(get_term))
+        .merge(debug_router(&config))
+        .route_layer(axum::middleware::from_fn(
+            request_id::add_request_id_to_errors,
+        ))
+        .layer(RequestBodyLimitLayer::new(max_body_bytes))
+        .layer(cors)
+        .layer(axum::middleware::from_fn(request_id::propagate_request_id))
+        .with_state(config)
+}
+
+/// Debug endpoints are only mounted when `debug_endpoints` (`DEBUG_ENDPOINTS`) is set.
+fn debug_router(config: &config::Config) -> Router<Arc<config::Config>> {
+    if !config.debug_endpoints {
+        return Router::new();
+    }
+    Router::new()
+        .route("/debug/cache", get(get_debug_cache))
+        .route("/debug/preview", axum::routing::post(post_debug_preview))
+}
+
+pub async fn get_debug_cache() -> impl IntoResponse {
+    axum::Json(lock_cache().debug_snapshot())
+}
+
+/// Downloads and parses a menu PDF without touching the live index, so staff
+/// can check a new upload before it is published.
+pub async fn post_debug_preview(
+    axum::Json(request): axum::Json<PreviewRequest>,
+) -> impl IntoResponse {
+    let url = request.url.trim();
+    let client = match build_client() {
+        Ok(client) => client,
+        Err(err) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to build HTTP client: {err}"),
+            )
+                .into_response();
+        }
+    };
+
+    match fetch_text_uncached(&client, url).await {
+        Ok(text) => axum::Json(preview_menu(url, &text)).into_response(),
+        Err(err) => (
+            StatusCode::BAD_GATEWAY,
+            format!("Failed to fetch menu PDF: {err}"),
+        )
+            .into_response(),
+    }
+}
+
+pub fn preview_menu(url: &str, text: &str) -> PreviewResponse {
+    let (week_start, week_source) = match parse_week_commencing_from_pdf_text(text) {
+        Some(week_start) => (Some(week_start), Some("pdf")),
+        None => match parse_week_commencing_from_filename(url) {
+            Some(week_start) => (Some(week_start), Some("filename")),
+            None => (None, None),
+        },
+    };
+    let (mut menus, parse) = match week_start {
+        Some(week_start) => {
+            let (menus, parse) = parse_weekly_menu_with_diagnostics(
+                text,
+                week_start,
+                section_keywords(),
+                week_start_day(),
+            );
+            (menus, Some(parse))
+        }
+        None => (HashMap::new(), None),
+    };
+    if menus.is_empty()
+        && looks_like_single_day_menu(text)
+        && let Some(date) = find_menu_date(text).or(week_start)
+    {
+        menus = parse_single_day_menu(text, date);
+    }
+
+    PreviewResponse {
+        url: url.to_string(),
+        week_start: week_start.map(format_output_date),
+        diagnostics: PreviewDiagnostics {
+            text_length: text.len(),
+            line_count: text.lines().count(),
+            week_source,
+            weeks_in_pdf: split_week_sections(text).len().max(1),
+            coverage: week_start.map(|week_start| week_coverage(&menus, week_start)),
+            parse,
+        },
+        menus: menus.into_iter().collect(),
+        notes: parse_week_notes(text),
+    }
+}
+
+/// Parses a comma-separated `CORS_ORIGINS` value. `None` means any origin is allowed.
+pub fn parse_cors_origins(raw: &str) -> anyhow::Result<Option<Vec<HeaderValue>>> {
+    let raw = raw.trim();
+    if raw.is_empty() || raw == "*" {
+        return Ok(None);
+    }
+
+    let mut origins = Vec::new();
+    for origin in raw.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        let url = Url::parse(origin)
+            .map_err(|err| anyhow::anyhow!("Invalid CORS origin {origin:?}: {err}"))?;
+        if !matches!(url.scheme(), "http" | "https") || url.host_str().is_none() {
+            anyhow::bail!("Invalid CORS origin {origin:?}: expected http(s)://host[:port]");
+        }
+        // Browsers send the origin without a trailing slash or path.
+        let normalized = url.origin().ascii_serialization();
+        origins.push(HeaderValue::from_str(&normalized)?);
+    }
+    Ok(Some(origins))
+}
+
+pub fn cors_layer(config: &config::Config) -> anyhow::Result<CorsLayer> {
+    let allow_origin = match parse_cors_origins(&config.cors_origins)? {
+        None => {
+            tracing::info!("CORS: allowing any origin");
+            AllowOrigin::any()
+        }
+        Some(origins) => {
+            tracing::info!("CORS: allowing origins {origins:?}");
+            AllowOrigin::list(origins)
+        }
+    };
+
+    Ok(CorsLayer::new()
+        .allow_origin(allow_origin)
+        .allow_methods([Method::GET, Method::POST])
+        .allow_headers([header::CONTENT_TYPE])
+        .expose_headers([
+            request_id::REQUEST_ID_HEADER.clone(),
+            header::HeaderName::from_static("x-data-stale"),
+        ]))
+}
+
+pub const BASE_URL: &str = "https://www.cranbrookschool.co.uk/";
+pub const CATERING_PAGE_URL: &str =
+    "https://www.cranbrookschool.co.uk/school-information/cranbrook-catering/";
+
+/// The configured catering page, `CATERING_PAGE_URL` by default.
+pub fn catering_page_url() -> String {
+    config::config().catering_page_url.clone()
+}
+
+/// The menu type of a catering page listed without one.
+pub const DEFAULT_MENU_TYPE: &str = "senior";
+
+/// A catering page and the menu type (senior, prep, ...) it lists.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CateringPage {
+    pub url: String,
+    pub menu_type: String,
+}
+
+/// Every catering page to scrape: the comma-separated `catering_page_urls`
+/// (`CATERING_PAGE_URLS`), or just `catering_page_url` when that is empty.
+pub fn catering_pages() -> Vec<CateringPage> {
+    let config = config::config();
+    parse_catering_pages(&config.catering_page_urls, &config.catering_page_url)
+}
+
+pub fn catering_page_urls() -> Vec<String> {
+    catering_pages().into_iter().map(|page| page.url).collect()
+}
+
+/// The menu type requests are answered from: the first catering page's.
+pub fn primary_menu_type() -> String {
+    catering_pages()
+        .into_iter()
+        .next()
+        .map_or_else(|| DEFAULT_MENU_TYPE.to_string(), |page| page.menu_type)
+}
+
+/// Index key for `key` of a `menu_type` menu: other types than `primary` are
+/// prefixed, e.g. `prep:2026-01-26-lunch`, so they don't overwrite its meals.
+pub fn menu_key(menu_type: &str, key: &str, primary: &str) -> String {
+    if menu_type == primary {
+        key.to_string()
+    } else {
+        format!("{menu_type}:{key}")
+    }
+}
+
+/// Entries may name their menu type, e.g. `prep=https://…/prep/catering/`;
+/// the rest are `DEFAULT_MENU_TYPE`.
+pub fn parse_catering_pages(raw: &str, default: &str) -> Vec<CateringPage> {
+    let mut pages: Vec<CateringPage> = Vec::new();
+    for entry in raw
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+    {
+        let (menu_type, url) = match entry.split_once('=') {
+            // A '=' after the scheme belongs to the URL's query.
+            Some((menu_type, url)) if !menu_type.contains('/') && !menu_type.trim().is_empty() => {
+                (menu_type.trim().to_lowercase(), url.trim())
+            }
+            _ => (DEFAULT_MENU_TYPE.to_string(), entry),
+        };
+        if !pages.iter().any(|seen| seen.url == url) {
+            pages.push(CateringPage {
+                url: url.to_string(),
+                menu_type,
+            });
+        }
+    }
+    if pages.is_empty() {
+        pages.push(CateringPage {
+            url: default.to_string(),
+            menu_type: DEFAULT_MENU_TYPE.to_string(),
+        });
+    }
+    pages
+}
+
+/// Hosts menu PDFs may be fetched from: those of the catering pages plus any
+/// in `allowed_pdf_hosts`.
+pub fn allowed_hosts() -> Vec<String> {
+    parse_allowed_hosts(&catering_page_urls(), &config::config().allowed_pdf_hosts)
+}
+
+pub fn parse_allowed_hosts(page_urls: &[String], extra: &str) -> Vec<String> {
+    let mut hosts: Vec<String> = Vec::new();
+    let page_hosts = page_urls
+        .iter()
+        .filter_map(|url| Url::parse(url).ok())
+        .filter_map(|url| url.host_str().map(str::to_lowercase));
+    let extra_hosts = extra
+        .split(',')
+        .map(|host| host.trim().to_lowercase())
+        .filter(|host| !host.is_empty());
+    for host in page_hosts.chain(extra_hosts) {
+        if !hosts.contains(&host) {
+            hosts.push(host);
+        }
+    }
+    hosts
+}
+
+pub fn is_allowed_url(url: &Url, hosts: &[String]) -> bool {
+    url.scheme() == "https"
+        && url.host_str().is_some_and(|host| {
+            hosts
+                .iter()
+                .any(|allowed| allowed.eq_ignore_ascii_case(host))
+        })
+}
+
+pub const DEFAULT_USER_AGENT: &str = "cranbrook-catering-api/0.1";
+
+/// The configured user agent verbatim when set, otherwise the default
+/// followed by the crate version.
+pub fn user_agent() -> String {
+    user_agent_for(Some(&config::config().user_agent))
+}
+
+fn user_agent_for(configured: Option<&str>) -> String {
+    match configured.map(str::trim).filter(|value| !value.is_empty()) {
+        Some(value) => value.to_string(),
+        None => format!("{DEFAULT_USER_AGENT} (v{})", env!("CARGO_PKG_VERSION")),
+    }
+}
+
+/// The `From` header for `CONTACT_EMAIL`; an unusable value is ignored
+/// rather than stopping the client from being built.
+fn contact_header(configured: Option<&str>) -> Option<reqwest::header::HeaderValue> {
+    let email = configured
+        .map(str::trim)
+        .filter(|value| !value.is_empty())?;
+    match reqwest::header::HeaderValue::from_str(email) {
+        Ok(value) if email.contains('@') => Some(value),
+        _ => {
+            tracing::warn!(email, "Ignoring invalid CONTACT_EMAIL");
+            None
+        }
+    }
+}
+
+pub fn build_client() -> anyhow::Result<Client> {
+    let hosts = allowed_hosts();
+
+    let mut headers = reqwest::header::HeaderMap::new();
+    if let Some(from) = contact_header(Some(&config::config().contact_email)) {
+        headers.insert(reqwest::header::FROM, from);
+    }
+
+    let client = Client::builder()
+        // Avoid macOS system proxy lookup that can panic in sandboxed contexts.
+        .no_proxy()
+        .user_agent(user_agent())
+        .default_headers(headers)
+        .timeout(std::time::Duration::from_secs(
+            config::config().request_timeout_secs,
+        ))
+        // Never follow redirects off the allowed hosts or down to plain HTTP.
+        .redirect(reqwest::redirect::Policy::custom(move |attempt| {
+            if attempt.previous().len() >= 10 {
+                attempt.error("too many redirects")
+            } else if is_allowed_url(attempt.url(), &hosts) {
+                attempt.follow()
+            } else {
+                let message = format!("redirect to disallowed URL {}", attempt.url());
+                attempt.error(message)
+            }
+        }))
+        .build()?;
+    Ok(client)
+}
+
+/// GETs `url`, optionally replaying stored validators. Returns `None` on 304 Not Modified.
+pub async fn conditional_get(
+    client: &Client,
+    url: &str,
+    use_validators: bool,
+) -> anyhow::Result<Option<Vec<u8>>> {
+    let mut request = client.get(url);
+    if use_validators {
+        let validators = lock_cache().validators(url);
+        if let Some(etag) = &validators.etag {
+            request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &validators.last_modified {
+            request = request.header(reqwest::header::IF_MODIFIED_SINCE, last_modified);
+        }
+    }
+
+    let response = request.send().await?;
+    if response.status() == reqwest::StatusCode::NOT_MODIFIED {
+        return Ok(None);
+    }
+    let response = response.error_for_status()?;
+
+    let header_value = |name: reqwest::header::HeaderName| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string)
+    };
+    let validators = Validators {
+        etag: header_value(reqwest::header::ETAG),
+        last_modified: header_value(reqwest::header::LAST_MODIFIED),
+    };
+    let body = response.bytes().await?.to_vec();
+    lock_cache().set_validators(url, validators);
+    Ok(Some(body))
+}
+
+/// Scrapes every configured catering page, keeping the first occurrence of a
+/// link that several pages share. The page and menu type each link came from
+/// are recorded in the cache. A page that can't be fetched is skipped unless
+/// every page fails.
+pub async fn fetch_menu_links(client: &Client) -> anyhow::Result<Vec<(String, Option<NaiveDate>)>> {
+    let hosts = allowed_hosts();
+
+    let catering_pages = catering_pages();
+    let mut pages = Vec::new();
+    let mut last_error = None;
+    for catering_page in &catering_pages {
+        // Relative links resolve against the page they appear on.
+        let fetched = match Url::parse(&catering_page.url) {
+            Ok(base) => landing_page(client, &catering_page.url)
+                .await
+                .map(|page| page_menu_links(&page, &catering_page.url, &base, &hosts)),
+            Err(err) => Err(err.into()),
+        };
+        match fetched {
+            Ok(links) => pages.push((catering_page.clone(), links)),
+            Err(err) => {
+                tracing::warn!("Skipping catering page {}: {err}", catering_page.url);
+                last_error = Some(err);
+            }
+        }
+    }
+    if pages.is_empty()
+        && let Some(err) = last_error
+    {
+        return Err(err);
+    }
+
+    let (links, sources) = merge_page_links(pages);
+    if links.is_empty() {
+        let page_urls: Vec<&str> = catering_pages
+            .iter()
+            .map(|page| page.url.as_str())
+            .collect();
+        tracing::error!(
+            "No menu links found on {page_urls:?}; the catering page layout may have changed"
+        );
+    }
+    {
+        let mut cache = lock_cache();
+        cache.set_link_sources(sources);
+        cache.set_no_menus_found(links.is_empty());
+    }
+    Ok(links)
+}
+
+/// The PDF links and menu tables on one catering page.
+pub fn page_menu_links(
+    page: &str,
+    page_url: &str,
+    base: &Url,
+    hosts: &[String],
+) -> Vec<(String, Option<NaiveDate>)> {
+    let mut links = extract_menu_links(page, base, hosts);
+    // Menus embedded as HTML tables are addressed by a fragment on the page URL.
+    for (index, table) in extract_menu_tables(page).into_iter().enumerate() {
+        match table.week_start {
+            Some(week_start) => links.push((menu_table_link(page_url, index), Some(week_start))),
+            None => tracing::warn!(
+                "Skipping menu table {index} on {page_url} - could not parse week start date"
+            ),
+        }
+    }
+    links
+}
+
+/// Concatenates each page's links in page order, dropping a link already seen
+/// on an earlier page. Also returns the page each kept link came from.
+pub fn merge_page_links(
+    pages: Vec<(CateringPage, Vec<(String, Option<NaiveDate>)>)>,
+) -> (
+    Vec<(String, Option<NaiveDate>)>,
+    HashMap<String, LinkSource>,
+) {
+    let mut links = Vec::new();
+    let mut sources: HashMap<String, LinkSource> = HashMap::new();
+    for (page, page_links) in pages {
+        for (link, week_start) in page_links {
+            if sources.contains_key(&link) {
+                tracing::debug!("{link} is also listed on {}", page.url);
+                continue;
+            }
+            sources.insert(
+                link.clone(),
+                LinkSource {
+                    page: page.url.clone(),
+                    menu_type: page.menu_type.clone(),
+                },
+            );
+            links.push((link, week_start));
+        }
+    }
+    (links, sources)
+}
+
+/// The catering page at `page_url`, served from cache while fresh.
+async fn landing_page(client: &Client, page_url: &str) -> anyhow::Result<String> {
+    let cached_page = lock_cache().landing_page_with_age(page_url);
+    Ok(match cached_page {
+        Some((html, age)) => match freshness_policy().classify(age) {
+            Freshness::Fresh => html,
+            Freshness::Stale => {
+                spawn_revalidation(page_url.to_string(), {
+                    let client = client.clone();
+                    let page_url = page_url.to_string();
+                    async move {
+                        revalidate_landing_page(&client, &page_url)
+                            .await
+                            .map(|_| ())
+                    }
+                });
+                html
+            }
+            Freshness::Expired => revalidate_landing_page(client, page_url).await?,
+        },
+        None => revalidate_landing_page(client, page_url).await?,
+    })
+}
+
+/// A `<table>` on the catering page that looks like a weekly menu.
+pub struct MenuTable {
+    pub html: String,
+    pub week_start: Option<NaiveDate>,
+}
+
+const MENU_TABLE_FRAGMENT: &str = "#menu-table-";
+
+pub fn menu_table_link(page_url: &str, index: usize) -> String {
+    format!("{page_url}{MENU_TABLE_FRAGMENT}{index}")
+}
+
+fn menu_table_index(link: &str) -> Option<usize> {
+    link.rsplit_once(MENU_TABLE_FRAGMENT)?.1.parse().ok()
+}
+
+/// The catering page a table link points into.
+fn menu_table_page(link: &str) -> &str {
+    link.rsplit_once(MENU_TABLE_FRAGMENT)
+        .map_or(link, |(page, _)| page)
+}
+
+fn weekday_from_header(text: &str) -> Option<Weekday> {
+    let word = text.trim().to_lowercase();
+    let word = word.split_whitespace().next()?;
+    ALL_DAYS
+        .into_iter()
+        .find(|day| word.starts_with(&day.to_string().to_lowercase()))
+}
+
+/// Finds tables whose header row names at least three weekdays, taking the
+/// week start from the table's own text or the elements just before it.
+pub fn extract_menu_tables(html: &str) -> Vec<MenuTable> {
+    let doc = Html::parse_document(html);
+    let table_selector = Selector::parse("table").unwrap();
+    let cell_selector = Selector::parse("tr:first-child th, tr:first-child td").unwrap();
+
+    doc.select(&table_selector)
+        .filter(|table| {
+            table
+                .select(&cell_selector)
+                .filter(|cell| weekday_from_header(&cell.text().collect::<String>()).is_some())
+                .count()
+                >= 3
+        })
+        .map(|table| {
+            let mut context = vec![table.text().collect::<Vec<_>>().join(" ")];
+            context.extend(
+                table
+                    .prev_siblings()
+                    .filter_map(scraper::ElementRef::wrap)
+                    .take(3)
+                    .map(|element| element.text().collect::<Vec<_>>().join(" ")),
+            );
+            let week_start = context.iter().find_map(|text| {
+                parse_week_commencing(text).or_else(|| parse_week_commencing_from_pdf_text(text))
+            });
+            MenuTable {
+                html: table.html(),
+                week_start,
+            }
+        })
+        .collect()
+}
+
+/// Parses a menu table with weekday columns and one row per period into the
+/// same "date-period" map the PDF parser produces.
+pub fn parse_weekly_menu_html(table_html: &str, week_start: NaiveDate) -> HashMap<String, String> {
+    let fragment = Html::parse_fragment(table_html);
+    let row_selector = Selector::parse("tr").unwrap();
+    let cell_selector = Selector::parse("th, td").unwrap();
+    let first_day = week_start_day();
+
+    let mut rows = fragment.select(&row_selector);
+    let Some(header) = rows.next() else {
+        return HashMap::new();
+    };
+    let columns: Vec<Option<Weekday>> = header
+        .select(&cell_selector)
+        .map(|cell| weekday_from_header(&cell.text().collect::<String>()))
+        .collect();
+
+    let mut out = HashMap::new();
+    for row in rows {
+        let cells: Vec<_> = row.select(&cell_selector).collect();
+        let Some(label) = cells.first() else {
+            continue;
+        };
+        let label = label.text().collect::<String>();
+        let Some(period) = period_from_label(&label) else {
+            continue;
+        };
+        for (cell, weekday) in cells.iter().zip(&columns).skip(1) {
+            let Some(weekday) = weekday else {
+                continue;
+            };
+            let meal = cell
+                .text()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .collect::<Vec<_>>()
+                .join("\n");
+            if meal.is_empty() {
+                continue;
+            }
+            let date = weekday_date(week_start, *weekday, first_day);
+            out.insert(format!("{}-{}", format_date(date), period.as_str()), meal);
+        }
+    }
+    out
+}
+
+/// Matches a row label such as "Lunch" or "Morning Break" to a period.
+fn period_from_label(label: &str) -> Option<MealPeriod> {
+    let label = label.trim().trim_end_matches(':');
+    label.parse::<MealPeriod>().ok().or_else(|| {
+        let keywords = section_keywords();
+        let lower = label.to_lowercase();
+        [
+            (MealPeriod::Breakfast, &keywords.breakfast),
+            (MealPeriod::Break, &keywords.morning_break),
+            (MealPeriod::Brunch, &keywords.brunch),
+            (MealPeriod::Lunch, &keywords.lunch),
+            (MealPeriod::Dinner, &keywords.dinner),
+        ]
+        .into_iter()
+        .find(|(_, words)| is_section_header(&lower, words))
+        .map(|(period, _)| period)
+    })
+}
+
+/// Re-fetches a catering page (conditionally, when a copy is cached) and stores it.
+pub async fn revalidate_landing_page(client: &Client, page_url: &str) -> anyhow::Result<String> {
+    let cached_page = lock_cache().landing_page(page_url);
+    let html = match conditional_get(client, page_url, cached_page.is_some()).await? {
+        Some(body) => String::from_utf8_lossy(&body).into_owned(),
+        None => cached_page.unwrap_or_default(),
+    };
+    lock_cache().set_landing_page(page_url, html.clone());
+    Ok(html)
+}
+
+/// Runs a background re-fetch for `key` unless one is already in flight.
+fn spawn_revalidation<F>(key: String, task: F)
+where
+    F: std::future::Future<Output = anyhow::Result<()>> + Send + 'static,
+{
+    if !lock_cache().begin_revalidation(&key) {
+        return;
+    }
+    tokio::spawn(async move {
+        if let Err(err) = task.await {
+            tracing::warn!("Background refresh of {key} failed: {err}");
+        }
+        lock_cache().end_revalidation(&key);
+    });
+}
+
+pub fn extract_menu_links(
+    html: &str,
+    base: &Url,
+    hosts: &[String],
+) -> Vec<(String, Option<NaiveDate>)> {
+    let doc = Html::parse_document(html);
+    let selector = Selector::parse("a").unwrap();
+
+    let mut links = Vec::new();
+    for element in doc.select(&selector) {
+        if let Some(href) = element.value().attr("href") {
+            let href_lower = href.to_lowercase();
+            if href_lower.contains(".pdf") && href_lower.contains("menu") {
+                let link = match base.join(href) {
+                    Ok(link) => link,
+                    Err(_) => continue,
+                };
+                if !is_allowed_url(&link, hosts) {
+                    tracing::warn!("Skipping off-host or non-HTTPS menu link {link}");
+                    continue;
+                }
+                let link_text = element.text().collect::<String>();
+                let week_date = parse_week_commencing(&link_text);
+                links.push((link.to_string(), week_date));
+            }
+        }
+    }
+
+    links
+}
+
+/// Parses a month name or common abbreviation ("January", "jan", "Sept.") to its number.
+pub fn month_from_str(input: &str) -> Option<u32> {
+    let lower = input.trim().trim_end_matches('.').to_lowercase();
+    let month = match lower.as_str() {
+        "january" | "jan" => Month::January,
+        "february" | "feb" => Month::February,
+        "march" | "mar" => Month::March,
+        "april" | "apr" => Month::April,
+        "may" => Month::May,
+        "june" | "jun" => Month::June,
+        "july" | "jul" => Month::July,
+        "august" | "aug" => Month::August,
+        "september" | "sep" | "sept" => Month::September,
+        "october" | "oct" => Month::October,
+        "november" | "nov" => Month::November,
+        "december" | "dec" => Month::December,
+        _ => return None,
+    };
+    Some(month.number_from_month())
+}
+
+pub fn parse_week_commencing(text: &str) -> Option<NaiveDate> {
+    // Parse "Menu for w/c Monday 26th January 2026" format
+    let re = regex::Regex::new(r"w/c\s+\w+\s+(\d+)(?:st|nd|rd|th)?\s+(\w+)\s+(\d{4})").ok()?;
+    let caps = re.captures(text)?;
+
+    let day = caps.get(1)?.as_str().parse::<u32>().ok()?;
+    let month_str = caps.get(2)?.as_str();
+    let year = caps.get(3)?.as_str().parse::<i32>().ok()?;
+
+    let month = month_from_str(month_str)?;
+
+    NaiveDate::from_ymd_opt(year, month, day)
+}
+
+pub fn parse_week_commencing_from_pdf_text(text: &str) -> Option<NaiveDate> {
+    find_week_commencing_dates(text)
+        .first()
+        .map(|(_, week_start)| *week_start)
+}
+
+/// Every week-commencing header in the PDF text with its byte offset, in order.
+pub fn find_week_commencing_dates(text: &str) -> Vec<(usize, NaiveDate)> {
+    // Handles variants such as:
+    // - "Week Commencing Monday 26th January 2026"
+    // - "w/c Monday 26 January 2026"
+    static RE: std::sync::LazyLock<regex::Regex> = std::sync::LazyLock::new(|| {
+        regex::Regex::new(
+            r"(?i)(?:week\s+commencing|w/c)\s+\w+\s+(\d+)(?:st|nd|rd|th)?\s+(\w+)\s+(\d{4})",
+        )
+        .expect("valid week-commencing regex")
+    });
+
+    RE.captures_iter(text)
+        .filter_map(|caps| {
+            let day = caps.get(1)?.as_str().parse::<u32>().ok()?;
+            let month = month_from_str(caps.get(2)?.as_str())?;
+            let year = caps.get(3)?.as_str().parse::<i32>().ok()?;
+            let week_start = NaiveDate::from_ymd_opt(year, month, day)?;
+            Some((caps.get(0)?.start(), week_start))
+        })
+        .collect()
+}
+
+/// The first full date in the text, e.g. "Thursday 17th December 2026", for
+/// menus that cover a single day rather than a week.
+pub fn find_menu_date(text: &str) -> Option<NaiveDate> {
+    // Also run against every line of a one-day menu, so compiled once.
+    static RE: std::sync::LazyLock<regex::Regex> = std::sync::LazyLock::new(|| {
+        regex::Regex::new(r"(?i)\b(\d{1,2})(?:st|nd|rd|th)?\s+([a-z]+)\s+(\d{4})\b")
+            .expect("valid menu date regex")
+    });
+    RE.captures_iter(text).find_map(|caps| {
+        let day = caps.get(1)?.as_str().parse::<u32>().ok()?;
+        let month = month_from_str(caps.get(2)?.as_str())?;
+        let year = caps.get(3)?.as_str().parse::<i32>().ok()?;
+        NaiveDate::from_ymd_opt(year, month, day)
+    })
+}
+
+/// Splits a multi-week (e.g. fortnightly) PDF into one text section per distinct
+/// week-commencing header. Returns an empty list for single-week text.
+pub fn split_week_sections(text: &str) -> Vec<(NaiveDate, &str)> {
+    let mut headers = find_week_commencing_dates(text);
+    headers.dedup_by_key(|(_, week_start)| *week_start);
+    if headers.len() < 2 {
+        return Vec::new();
+    }
+
+    headers
+        .iter()
+        .enumerate()
+        .map(|(i, (offset, week_start))| {
+            // Anything before the first header (titles) belongs to the first week.
+            let start = if i == 0 { 0 } else { *offset };
+            let end = headers.get(i + 1).map_or(text.len(), |(next, _)| *next);
+            (*week_start, &text[start..end])
+        })
+        .collect()
+}
+
+/// Extracts a date from the last path segment of a PDF URL, e.g.
+/// `Week-Menu-26-01-2026.pdf`, `menu_2026-01-26.pdf` or `Menu-26Jan2026.pdf`.
+pub fn parse_week_commencing_from_filename(url: &str) -> Option<NaiveDate> {
+    let path = url.split(['?', '#']).next()?;
+    let name = path.rsplit('/').next()?.replace("%20", "-");
+
+    static ISO: std::sync::LazyLock<regex::Regex> = std::sync::LazyLock::new(|| {
+        regex::Regex::new(r"(?:^|[^0-9])(\d{4})[-_.](\d{1,2})[-_.](\d{1,2})(?:[^0-9]|$)")
+            .expect("valid ISO filename date regex")
+    });
+    static DMY: std::sync::LazyLock<regex::Regex> = std::sync::LazyLock::new(|| {
+        regex::Regex::new(r"(?:^|[^0-9])(\d{1,2})[-_.](\d{1,2})[-_.](\d{4})(?:[^0-9]|$)")
+            .expect("valid day-month-year filename date regex")
+    });
+    static NAMED: std::sync::LazyLock<regex::Regex> = std::sync::LazyLock::new(|| {
+        regex::Regex::new(
+            r"(?i)(?:^|[^0-9])(\d{1,2})(?:st|nd|rd|th)?[-_.]?([a-z]{3,9})[-_.]?(\d{4})(?:[^0-9]|$)",
+        )
+        .expect("valid named-month filename date regex")
+    });
+
+    if let Some(caps) = ISO.captures(&name) {
+        let date = NaiveDate::from_ymd_opt(
+            caps[1].parse().ok()?,
+            caps[2].parse().ok()?,
+            caps[3].parse().ok()?,
+        );
+        if date.is_some() {
+            return date;
+        }
+    }
+
+    if let Some(caps) = DMY.captures(&name) {
+        let date = NaiveDate::from_ymd_opt(
+            caps[3].parse().ok()?,
+            caps[2].parse().ok()?,
+            caps[1].parse().ok()?,
+        );
+        if date.is_some() {
+            return date;
+        }
+    }
+
+    let caps = NAMED.captures(&name)?;
+    NaiveDate::from_ymd_opt(
+        caps[3].parse().ok()?,
+        month_from_str(&caps[2])?,
+        caps[1].parse().ok()?,
+    )
+}
+
+/// Returns `None` rather than guess when the nearest published week is more
+/// than `max_inference_weeks` from the inferred target week.
+fn choose_inferred_week_start(
+    week_starts: &[NaiveDate],
+    requested_date: NaiveDate,
+    today: NaiveDate,
+    max_inference_weeks: u32,
+) -> Option<WeekResolution> {
+    if week_starts.is_empty() {
+        return None;
+    }
+
+    // Prefer an exact containing week first.
+    for week_start in week_starts {
+        let week_end = *week_start + chrono::Duration::days(6);
+        if requested_date >= *week_start && requested_date <= week_end {
+            return Some(WeekResolution::Exact(*week_start));
+        }
+    }
+
+    // Infer target menu week from the menu week that best represents "today".
+    let today_week = week_starts
+        .iter()
+        .min_by_key(|candidate| (today - **candidate).num_days().abs())
+        .copied()?;
+
+    let delta_weeks = (requested_date - today).num_days().div_euclid(7);
+    let inferred_target = today_week + chrono::Duration::days(delta_weeks * 7);
+
+    let nearest = week_starts
+        .iter()
+        .min_by_key(|candidate| (inferred_target - **candidate).num_days().abs())
+        .copied()?;
+    if (inferred_target - nearest).num_days().abs() > i64::from(max_inference_weeks) * 7 {
+        tracing::debug!(
+            "Nearest week {nearest} is more than {max_inference_weeks} weeks from {inferred_target}"
+        );
+        return None;
+    }
+    Some(WeekResolution::Inferred(nearest))
+}
+
+pub const DATE_PARAM_YEARS: std::ops::RangeInclusive<i32> = 2000..=2100;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum DateParamError {
+    /// Not three numeric parts separated by `-` or `/`.
+    Format,
+    Year(i32),
+    Month(u32),
+    Day(u32),
+    /// Each part is in range but the date doesn't exist, e.g. 2026-02-30.
+    NotInCalendar {
+        year: i32,
+        month: u32,
+        day: u32,
+    },
+}
+
+impl std::fmt::Display for DateParamError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DateParamError::Format => {
+                write!(f, "Invalid date format. Use YYYY-MM-DD or YYYY/MM/DD.")
+            }
+            DateParamError::Year(year) => write!(
+                f,
+                "Invalid date: year must be {}–{} (got {year}).",
+                DATE_PARAM_YEARS.start(),
+                DATE_PARAM_YEARS.end()
+            ),
+            DateParamError::Month(month) => {
+                write!(f, "Invalid date: month must be 1–12 (got {month}).")
+            }
+            DateParamError::Day(day) => write!(f, "Invalid date: day must be 1–31 (got {day})."),
+            DateParamError::NotInCalendar { year, month, day } => write!(
+                f,
+                "Invalid date: {year:04}-{month:02}-{day:02} does not exist."
+            ),
+        }
+    }
+}
+
+impl std::error::Error for DateParamError {}
+
+pub fn parse_date_param(input: &str) -> Option<NaiveDate> {
+    try_parse_date_param(input).ok()
+}
+
+/// Like `parse_date_param`, but says which part of the date was wrong.
+pub fn try_parse_date_param(input: &str) -> Result<NaiveDate, DateParamError> {
+    let parts: Vec<_> = input.trim().split(|c| c == '-' || c == '/').collect();
+    let [year, month, day] = parts.as_slice() else {
+        return Err(DateParamError::Format);
+    };
+    let year = year.parse::<i32>().map_err(|_| DateParamError::Format)?;
+    let month = month.parse::<u32>().map_err(|_| DateParamError::Format)?;
+    let day = day.parse::<u32>().map_err(|_| DateParamError::Format)?;
+
+    if !DATE_PARAM_YEARS.contains(&year) {
+        return Err(DateParamError::Year(year));
+    }
+    if !(1..=12).contains(&month) {
+        return Err(DateParamError::Month(month));
+    }
+    if !(1..=31).contains(&day) {
+        return Err(DateParamError::Day(day));
+    }
+    NaiveDate::from_ymd_opt(year, month, day).ok_or(DateParamError::NotInCalendar {
+        year,
+        month,
+        day,
+    })
+}
+
+fn format_date(date: NaiveDate) -> String {
+    format!("{:04}-{:02}-{:02}", date.year(), date.month(), date.day())
+}
+
+/// Resolves a `DATE_OUTPUT_FORMAT` value: "iso" (default), "dmy" for DD/MM/YYYY,
+/// or a custom chrono format string. Invalid formats fall back to ISO.
+pub fn resolve_date_format(raw: &str) -> String {
+    let raw = raw.trim();
+    match raw.to_lowercase().as_str() {
+        "" | "iso" => "%Y-%m-%d".to_string(),
+        "dmy" | "dd/mm/yyyy" => "%d/%m/%Y".to_string(),
+        _ => {
+            let invalid = chrono::format::StrftimeItems::new(raw)
+                .any(|item| matches!(item, chrono::format::Item::Error));
+            if invalid {
+                tracing::warn!("Invalid DATE_OUTPUT_FORMAT {raw:?}; using ISO dates");
+                "%Y-%m-%d".to_string()
+            } else {
+                raw.to_string()
+            }
+        }
+    }
+}
+
+fn date_output_format() -> &'static str {
+    static FORMAT: std::sync::OnceLock<String> = std::sync::OnceLock::new();
+    FORMAT.get_or_init(|| resolve_date_format(&config::config().date_output_format))
+}
+
+/// Formats a date for responses. Index keys always use `format_date` (ISO).
+pub fn format_output_date(date: NaiveDate) -> String {
+    date.format(date_output_format()).to_string()
+}
+
+pub async fn download_and_extract_text(client: &Client, url: &str) -> anyhow::Result<String> {
+    let parsed = Url::parse(url)?;
+    if !is_allowed_url(&parsed, &allowed_hosts()) {
+        anyhow::bail!("Refusing to fetch menu from disallowed URL {url}");
+    }
+    let path = disk_cache_path(&cache_dir(), url);
+    let (bytes, from_disk) = match read_fresh_disk_copy(&path).await {
+        Some(bytes) => (bytes, true),
+        None => {
+            // Sent without validators, so there is no 304 to expect.
+            let Some(bytes) = conditional_get(client, url, false).await? else {
+                anyhow::bail!("No body returned for {url}");
+            };
+            (bytes, false)
+        }
+    };
+    let text = extract_pdf_text(&bytes, url)?;
+    // Only a PDF that extracted is worth serving from disk later.
+    if !from_disk && let Err(err) = write_atomically(&path, &bytes).await {
+        tracing::warn!("Failed to cache {url} at {}: {err}", path.display());
+    }
+    lock_cache().set_content_hash(url, content_hash(&bytes));
+    Ok(text)
+}
+
+/// Downloads and extracts a PDF without touching validators, content hashes or
+/// the disk cache, for previews that must not affect live state.
+pub async fn fetch_text_uncached(client: &Client, url: &str) -> anyhow::Result<String> {
+    let parsed = Url::parse(url)?;
+    if !is_allowed_url(&parsed, &allowed_hosts()) {
+        anyhow::bail!("Refusing to fetch menu from disallowed URL {url}");
+    }
+    let bytes = client
+        .get(url)
+        .send()
+        .await?
+        .error_for_status()?
+        .bytes()
+        .await?;
+    Ok(extract_pdf_text(&bytes, url)?)
+}
+
+/// Directory raw PDFs are cached in, from `cache_dir` (default a temp subdirectory).
+pub fn cache_dir() -> std::path::PathBuf {
+    match config::config().cache_dir.trim() {
+        "" => std::env::temp_dir().join("cranbrook-catering"),
+        dir => std::path::PathBuf::from(dir),
+    }
+}
+
+/// A filesystem-safe name for `url`: its sanitized tail plus a hash prefix so
+/// distinct URLs never collide.
+pub fn disk_cache_path(dir: &std::path::Path, url: &str) -> std::path::PathBuf {
+    let sanitized: String = url
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let tail = &sanitized[sanitized.len().saturating_sub(80)..];
+    let hash = content_hash(url.as_bytes());
+    dir.join(format!("{}-{}", &hash[..16], tail))
+}
+
+/// Reads a cached PDF if it was written within the cache TTL.
+async fn read_fresh_disk_copy(path: &std::path::Path) -> Option<Vec<u8>> {
+    let modified = tokio::fs::metadata(path).await.ok()?.modified().ok()?;
+    let age = modified.elapsed().unwrap_or_default();
+    if freshness_policy().classify(age) != Freshness::Fresh {
+        return None;
+    }
+    tokio::fs::read(path).await.ok()
+}
+
+/// Writes through a uniquely named temp file and renames it into place, so
+/// concurrent writers and readers never see a partial file.
+pub async fn write_atomically(path: &std::path::Path, bytes: &[u8]) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        tokio::fs::create_dir_all(dir).await?;
+    }
+    let tmp = path.with_extension(format!(
+        "{}.{:08x}.tmp",
+        std::process::id(),
+        rand::random::<u32>()
+    ));
+    tokio::fs::write(&tmp, bytes).await?;
+    if let Err(err) = tokio::fs::rename(&tmp, path).await {
+        let _ = tokio::fs::remove_file(&tmp).await;
+        return Err(err);
+    }
+    Ok(())
+}
+
+/// Hex SHA-256 of downloaded PDF bytes, used to spot unchanged re-downloads
+/// from origins that don't send useful caching headers.
+pub fn content_hash(bytes: &[u8]) -> String {
+    use sha2::Digest;
+    format!("{:x}", sha2::Sha256::digest(bytes))
+}
+
+#[derive(Debug)]
+pub enum CateringError {
+    /// The PDF was empty or yielded no text (corrupt file or wrong content served).
+    EmptyPdf {
+        url: String,
+    },
+    PdfExtract {
+        url: String,
+        message: String,
+    },
+    /// The date falls just after the latest published week (see `is_pending_publication`).
+    NotYetPublished {
+        date: NaiveDate,
+    },
+}
+
+impl std::fmt::Display for CateringError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CateringError::EmptyPdf { url } => write!(f, "No text could be extracted from {url}"),
+            CateringError::PdfExtract { url, message } => {
+                write!(f, "Failed to extract text from {url}: {message}")
+            }
+            CateringError::NotYetPublished { date } => {
+                write!(
+                    f,
+                    "The menu for {} hasn't been published yet",
+                    format_date(*date)
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for CateringError {}
+
+pub fn extract_pdf_text(bytes: &[u8], url: &str) -> Result<String, CateringError> {
+    let empty = || CateringError::EmptyPdf {
+        url: url.to_string(),
+    };
+    if bytes.is_empty() {
+        return Err(empty());
+    }
+    let text =
+        pdf_extract::extract_text_from_mem(bytes).map_err(|err| CateringError::PdfExtract {
+            url: url.to_string(),
+            message: err.to_string(),
+        })?;
+    if text.trim().is_empty() {
+        tracing::warn!("Extracted text from {url} is empty");
+        return Err(empty());
+    }
+    Ok(text)
+}
+
+pub fn is_junk_line(trimmed: &str, lower: &str) -> bool {
+    if trimmed.is_empty() {
+        return true;
+    }
+    if trimmed.chars().all(|c| !c.is_alphanumeric()) {
+        return true;
+    }
+    if trimmed == "\"" {
+        return true;
+    }
+    if lower.contains("cranbrook") || lower.contains("menu") || lower.contains("week commencing") {
+        return true;
+    }
+    if is_disclaimer_line(lower) || parse_last_updated(trimmed).is_some() {
+        return true;
+    }
+    false
+}
+
+/// Phrases that mark a line as a week-level disclaimer rather than a dish.
+const DISCLAIMER_PHRASES: [&str; 4] = [
+    "subject to change",
+    "subject to availability",
+    "please inform",
+    "please speak to",
+];
+
+pub fn is_disclaimer_line(lower: &str) -> bool {
+    DISCLAIMER_PHRASES
+        .iter()
+        .any(|phrase| lower.contains(phrase))
+}
+
+/// Parses "Last updated 12/01/2026" or "Updated: 12th January 2026" style lines.
+pub fn parse_last_updated(line: &str) -> Option<NaiveDate> {
+    // Checked for every PDF line via `is_junk_line`, so compiled once.
+    static RE: std::sync::LazyLock<regex::Regex> = std::sync::LazyLock::new(|| {
+        regex::Regex::new(
+            r"(?i)^(?:last\s+)?updated:?\s+(?:on\s+)?(?:(\d{1,2})[/.-](\d{1,2})[/.-](\d{4})|(\d{1,2})(?:st|nd|rd|th)?\s+(\w+)\s+(\d{4}))",
+        )
+        .expect("valid last-updated regex")
+    });
+    let caps = RE.captures(line.trim())?;
+
+    if let (Some(day), Some(month), Some(year)) = (caps.get(1), caps.get(2), caps.get(3)) {
+        return NaiveDate::from_ymd_opt(
+            year.as_str().parse().ok()?,
+            month.as_str().parse().ok()?,
+            day.as_str().parse().ok()?,
+        );
+    }
+    let day = caps.get(4)?.as_str().parse::<u32>().ok()?;
+    let month = month_from_str(caps.get(5)?.as_str())?;
+    let year = caps.get(6)?.as_str().parse::<i32>().ok()?;
+    NaiveDate::from_ymd_opt(year, month, day)
+}
+
+pub fn parse_week_notes(text: &str) -> WeekNotes {
+    let mut notes = WeekNotes::default();
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if let Some(date) = parse_last_updated(trimmed) {
+            notes
+                .last_updated
+                .get_or_insert_with(|| format_output_date(date));
+            continue;
+        }
+        let note = trimmed.split_whitespace().collect::<Vec<_>>().join(" ");
+        if is_disclaimer_line(&note.to_lowercase()) && !notes.notes.contains(&note) {
+            notes.notes.push(note);
+        }
+    }
+    notes
+}
+
+pub fn split_blocks(lines: &[String], expected_blocks: usize) -> Vec<Vec<String>> {
+    let mut blocks: Vec<Vec<String>> = Vec::new();
+    for raw in lines {
+        let trimmed = raw.trim();
+        let lower = trimmed.to_lowercase();
+        if is_junk_line(trimmed, &lower) {
+            continue;
+        }
+        let starts_new =
+            raw.starts_with(' ') && !blocks.is_empty() && blocks.len() < expected_blocks;
+        if starts_new {
+            blocks.push(Vec::new());
+        }
+        if blocks.is_empty() {
+            blocks.push(Vec::new());
+        }
+        blocks.last_mut().unwrap().push(trimmed.to_string());
+    }
+    blocks
+}
+
+/// Joins wrapped dish descriptions back onto the item they continue: a line
+/// starting lowercase or with a connector, or following a line that ends
+/// mid-phrase, is appended to the previous dish.
+pub fn merge_continuations(lines: &[String]) -> Vec<String> {
+    const CONNECTORS: [&str; 4] = ["&", "and", "with", "or"];
+
+    let mut merged: Vec<String> = Vec::new();
+    for line in lines {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        let first_word = trimmed.split_whitespace().next().unwrap_or_default();
+        let continues_line = trimmed.starts_with(|c: char| c.is_lowercase() || "(,&".contains(c))
+            || CONNECTORS.contains(&first_word.to_lowercase().as_str());
+        let previous_open = merged.last().is_some_and(|previous| {
+            let last_word = previous.split_whitespace().last().unwrap_or_default();
+            previous.ends_with(',') || CONNECTORS.contains(&last_word.to_lowercase().as_str())
+        });
+
+        match merged.last_mut() {
+            Some(previous) if continues_line || previous_open => {
+                previous.push(' ');
+                previous.push_str(trimmed);
+            }
+            _ => merged.push(trimmed.to_string()),
+        }
+    }
+    merged
+}
+
+pub fn fill_first_line_per_day(
+    lines: &[String],
+    dates: &[NaiveDate],
+    period: &str,
+    out: &mut HashMap<String, String>,
+) {
+    let mut found = vec![false; dates.len()];
+    for raw in lines {
+        let trimmed = raw.trim();
+        let lower = trimmed.to_lowercase();
+        if is_junk_line(trimmed, &lower) {
+            continue;
+        }
+        for (day, date) in dates.iter().enumerate() {
+            if !found[day] {
+                let key = format!("{}-{period}", format_date(date));
+                out.insert(key, trimmed.to_string());
+                found[day] = true;
+                break;
+            }
+        }
+    }
+}
+
+/// Words that mark each section header. Every synonym counts towards the
+/// canonical period, so a "Supper" header is read as dinner.
+#[derive(Clone, Debug)]
+pub struct SectionKeywords {
+    pub breakfast: Vec<String>,
+    /// Matched as whole words so "breakfast" never opens a break section.
+    pub morning_break: Vec<String>,
+    pub brunch: Vec<String>,
+    pub lunch: Vec<String>,
+    pub dinner: Vec<String>,
+}
+
+impl Default for SectionKeywords {
+    fn default() -> Self {
+        SectionKeywords {
+            breakfast: vec!["breakfast".to_string()],
+            morning_break: vec![
+                "morning break".to_string(),
+                "break".to_string(),
+                "elevenses".to_string(),
+            ],
+            brunch: vec!["brunch".to_string()],
+            lunch: vec!["lunch".to_string()],
+            dinner: vec!["dinner".to_string()],
+        }
+    }
+}
+
+impl SectionKeywords {
+    /// Defaults extended with the comma-separated synonyms in `breakfast_headers`,
+    /// `break_headers`, `brunch_headers`, `lunch_headers` and `dinner_headers`.
+    pub fn from_config(config: &config::Config) -> Self {
+        let mut keywords = SectionKeywords::default();
+        for (synonyms, words) in [
+            (&config.breakfast_headers, &mut keywords.breakfast),
+            (&config.break_headers, &mut keywords.morning_break),
+            (&config.brunch_headers, &mut keywords.brunch),
+            (&config.lunch_headers, &mut keywords.lunch),
+            (&config.dinner_headers, &mut keywords.dinner),
+        ] {
+            words.extend(
+                synonyms
+                    .split(',')
+                    .map(|word| word.trim().to_lowercase())
+                    .filter(|word| !word.is_empty()),
+            );
+        }
+        keywords
+    }
+}
+
+pub fn section_keywords() -> &'static SectionKeywords {
+    static KEYWORDS: std::sync::OnceLock<SectionKeywords> = std::sync::OnceLock::new();
+    KEYWORDS.get_or_init(|| SectionKeywords::from_config(&config::config()))
+}
+
+fn count_keywords(lower: &str, keywords: &[String]) -> usize {
+    keywords
+        .iter()
+        .map(|keyword| lower.matches(keyword.as_str()).count())
+        .sum()
+}
+
+const WEEKDAY_WORDS: [&str; 14] = [
+    "monday",
+    "tuesday",
+    "wednesday",
+    "thursday",
+    "friday",
+    "saturday",
+    "sunday",
+    "mon",
+    "tue",
+    "wed",
+    "thu",
+    "fri",
+    "sat",
+    "sun",
+];
+
+/// A header either starts with the keyword and carries nothing else but
+/// repeats, day names or a column count ("Breakfast", "Lunch 5", "Dinner Mon
+/// Tue ..."), or, when that is ambiguous, repeats the keyword once per column.
+fn is_section_header(lower: &str, keywords: &[String]) -> bool {
+    let positional = keywords.iter().any(|keyword| {
+        let Some(rest) = lower.strip_prefix(keyword.as_str()) else {
+            return false;
+        };
+        if rest.starts_with(char::is_alphanumeric) {
+            return false;
+        }
+        rest.split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .all(|word| {
+                word.chars().all(|c| c.is_ascii_digit())
+                    || WEEKDAY_WORDS.contains(&word)
+                    || keywords.iter().any(|keyword| keyword == word)
+            })
+    });
+    positional || count_keywords(lower, keywords) >= 3
+}
+
+/// Brunch headers may also be labelled with their day ("Saturday Brunch"), so
+/// leading day names are skipped before the positional check.
+fn is_brunch_header(lower: &str, keywords: &[String]) -> bool {
+    let mut rest = lower.trim_start();
+    while let Some((word, tail)) = rest.split_once(char::is_whitespace)
+        && WEEKDAY_WORDS.contains(&word)
+    {
+        rest = tail.trim_start();
+    }
+    is_section_header(rest, keywords)
+}
+
+/// Like `count_keywords`, but only counts matches not embedded in a longer word.
+fn count_whole_words(lower: &str, keywords: &[String]) -> usize {
+    keywords
+        .iter()
+        .map(|keyword| {
+            lower
+                .match_indices(keyword.as_str())
+                .filter(|(start, matched)| {
+                    let before = lower[..*start].chars().next_back();
+                    let after = lower[start + matched.len()..].chars().next();
+                    !before.is_some_and(char::is_alphanumeric)
+                        && !after.is_some_and(char::is_alphanumeric)
+                })
+                .count()
+        })
+        .sum()
+}
+
+pub fn parse_weekly_menu(text: &str, week_start: NaiveDate) -> HashMap<String, String> {
+    parse_weekly_menu_with(text, week_start, section_keywords(), week_start_day())
+}
+
+/// A one-day menu (an event dinner, a themed lunch) is short and has no
+/// weekday columns; text that is neither is a weekly menu that failed to parse.
+pub const MAX_SINGLE_DAY_LINES: usize = 30;
+
+pub fn looks_like_single_day_menu(text: &str) -> bool {
+    let lower = text.to_lowercase();
+    let weekdays = WEEKDAY_WORDS[..7]
+        .iter()
+        .filter(|day| lower.contains(*day))
+        .count();
+    let lines = text.lines().filter(|line| !line.trim().is_empty()).count();
+    weekdays < 3 && lines <= MAX_SINGLE_DAY_LINES
+}
+
+/// Parses a one-day menu (a Christmas dinner, a themed event) that has no
+/// weekday columns. Lines are grouped under any period headers and stored as
+/// one block per period on `date`; without headers the whole menu goes under
+/// the first period named anywhere in the text, or dinner.
+pub fn parse_single_day_menu(text: &str, date: NaiveDate) -> HashMap<String, String> {
+    let mut sections: Vec<(MealPeriod, Vec<String>)> = Vec::new();
+    let mut untitled = Vec::new();
+
+    for line in text.lines() {
+        let trimmed = line.trim();
+        let lower = trimmed.to_lowercase();
+        if is_junk_line(trimmed, &lower) || find_menu_date(trimmed).is_some() {
+            continue;
+        }
+        if let Some(period) = period_from_label(trimmed) {
+            sections.push((period, Vec::new()));
+            continue;
+        }
+        match sections.last_mut() {
+            Some((_, lines)) => lines.push(trimmed.to_string()),
+            None => untitled.push(trimmed.to_string()),
+        }
+    }
+
+    if sections.is_empty() {
+        let lower = text.to_lowercase();
+        let keywords = section_keywords();
+        let period = [
+            (MealPeriod::Breakfast, &keywords.breakfast),
+            (MealPeriod::Brunch, &keywords.brunch),
+            (MealPeriod::Lunch, &keywords.lunch),
+            (MealPeriod::Dinner, &keywords.dinner),
+        ]
+        .into_iter()
+        .find(|(_, words)| count_whole_words(&lower, words) > 0)
+        .map_or(MealPeriod::Dinner, |(period, _)| period);
+        sections.push((period, untitled));
+    }
+
+    let mut out = HashMap::new();
+    for (period, lines) in sections {
+        if lines.is_empty() {
+            continue;
+        }
+        let key = format!("{}-{}", format_date(date), period.as_str());
+        let block = merge_continuations(&lines).join("\n");
+        out.entry(key)
+            .and_modify(|existing: &mut String| {
+                existing.push('\n');
+                existing.push_str(&block);
+            })
+            .or_insert(block);
+    }
+    out
+}
+
+pub fn parse_weekly_menu_with(
+    text: &str,
+    week_start: NaiveDate,
+    keywords: &SectionKeywords,
+    first_day: Weekday,
+) -> HashMap<String, String> {
+    parse_weekly_menu_with_diagnostics(text, week_start, keywords, first_day).0
+}
+
+/// How the parser walked a PDF, for spotting layout changes.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct ParseDiagnostics {
+    /// Section headers in the order they were met, one list per week in the PDF.
+    pub section_order: Vec<Vec<String>>,
+    /// False when any week's sections deviate from the expected order.
+    pub section_order_ok: bool,
+}
+
+/// The configured expected section order (`EXPECTED_SECTION_ORDER`), defaulting
+/// to breakfast, break, brunch, lunch, dinner.
+pub fn expected_section_order() -> Vec<MealPeriod> {
+    let configured: Vec<MealPeriod> = config::config()
+        .expected_section_order
+        .split(',')
+        .filter_map(|period| period.trim().parse().ok())
+        .collect();
+    if configured.is_empty() {
+        MealPeriod::ALL.to_vec()
+    } else {
+        configured
+    }
+}
+
+/// True when `observed` visits sections in `expected` order, each at most once.
+pub fn section_order_matches(observed: &[MealPeriod], expected: &[MealPeriod]) -> bool {
+    let mut remaining = expected.iter();
+    observed
+        .iter()
+        .all(|period| remaining.any(|expected| expected == period))
+}
+
+pub fn parse_weekly_menu_with_diagnostics(
+    text: &str,
+    week_start: NaiveDate,
+    keywords: &SectionKeywords,
+    first_day: Weekday,
+) -> (HashMap<String, String>, ParseDiagnostics) {
+    let sections = split_week_sections(text);
+    let weeks = if sections.is_empty() {
+        vec![(week_start, text)]
+    } else {
+        sections
+    };
+
+    let expected = expected_section_order();
+    let mut out = HashMap::new();
+    let mut diagnostics = ParseDiagnostics {
+        section_order: Vec::new(),
+        section_order_ok: true,
+    };
+    for (section_start, section) in weeks {
+        let (menus, order) = parse_single_week(section, section_start, keywords, first_day);
+        out.extend(menus);
+        if !section_order_matches(&order, &expected) {
+            tracing::warn!(
+                "Unexpected section order {order:?} for week {}; sections may be misparsed",
+                format_date(section_start)
+            );
+            diagnostics.section_order_ok = false;
+        }
+        diagnostics.section_order.push(
+            order
+                .iter()
+                .map(|period| period.as_str().to_string())
+                .collect(),
+        );
+    }
+    (out, diagnostics)
+}
+
+fn parse_single_week(
+    text: &str,
+    week_start: NaiveDate,
+    keywords: &SectionKeywords,
+    first_day: Weekday,
+) -> (HashMap<String, String>, Vec<MealPeriod>) {
+    let mut out = HashMap::new();
+    let mut order: Vec<MealPeriod> = Vec::new();
+    let mut enter = |period: MealPeriod| {
+        if order.last() != Some(&period) {
+            order.push(period);
+        }
+    };
+    // Breakfast and lunch columns run Mon-Fri; dinner columns follow the week's own order.
+    let weekday_dates: Vec<NaiveDate> = WEEKDAYS
+        .iter()
+        .map(|day| weekday_date(week_start, *day, first_day))
+        .collect();
+    let all_dates: Vec<NaiveDate> = (0..7)
+        .map(|day| week_start + chrono::Duration::days(day))
+        .collect();
+    let lines: Vec<_> = text.lines().collect();
+
+    // The PDF has a table structure where days are columns
+    // We need to track which section (breakfast/brunch/lunch/dinner) we're in
+    // and collect its lines, which are then split into one block per day
+
+    let mut in_breakfast = false;
+    let mut in_break = false;
+    let mut in_brunch = false;
+    let mut in_lunch = false;
+    let mut in_dinner = false;
+
+    let mut breakfast_lines: Vec<String> = Vec::new();
+    // One entry per brunch header seen; a single merged weekend header yields one.
+    let mut brunch_sections: Vec<Vec<String>> = Vec::new();
+    let mut break_lines: Vec<String> = Vec::new();
+    let mut lunch_lines: Vec<String> = Vec::new();
+    let mut dinner_lines: Vec<String> = Vec::new();
+
+    for line in lines {
+        let trimmed = line.trim();
+        let lower = trimmed.to_lowercase();
+
+        // Detect section headers - look for lines with multiple instances of the period name
+        if is_section_header(&lower, &keywords.breakfast) {
+            enter(MealPeriod::Breakfast);
+            in_breakfast = true;
+            in_break = false;
+            in_brunch = false;
+            in_lunch = false;
+            in_dinner = false;
+            continue;
+        }
+        // Break is usually a single row label, so only a line led by the keyword
+        // counts; "Half Term Break" or a dish mentioning a break does not.
+        if is_section_header(&lower, &keywords.morning_break) {
+            in_breakfast = false;
+            enter(MealPeriod::Break);
+            in_break = true;
+            in_brunch = false;
+            in_lunch = false;
+            in_dinner = false;
+            continue;
+        }
+        if is_brunch_header(&lower, &keywords.brunch) {
+            // A repeated header inside the section starts the Sunday column.
+            if !in_brunch
+                || brunch_sections
+                    .last()
+                    .is_some_and(|section| !section.is_empty())
+            {
+                brunch_sections.push(Vec::new());
+            }
+            in_breakfast = false;
+            in_break = false;
+            enter(MealPeriod::Brunch);
+            in_brunch = true;
+            in_lunch = false;
+            in_dinner = false;
+            continue;
+        }
+        if is_section_header(&lower, &keywords.lunch) {
+            in_breakfast = false;
+            in_break = false;
+            in_brunch = false;
+            enter(MealPeriod::Lunch);
+            in_lunch = true;
+            in_dinner = false;
+            continue;
+        }
+        if is_section_header(&lower, &keywords.dinner) {
+            in_breakfast = false;
+            in_break = false;
+            in_brunch = false;
+            in_lunch = false;
+            enter(MealPeriod::Dinner);
+            in_dinner = true;
+            continue;
+        }
+
+        if in_breakfast {
+            breakfast_lines.push(line.to_string());
+        } else if in_break {
+            break_lines.push(line.to_string());
+        } else if in_lunch {
+            lunch_lines.push(line.to_string());
+        } else if in_dinner {
+            dinner_lines.push(line.to_string());
+        } else if in_brunch && let Some(section) = brunch_sections.last_mut() {
+            section.push(line.to_string());
+        }
+    }
+
+    let breakfast_blocks = split_blocks(&breakfast_lines, 5);
+    if breakfast_blocks.len() == 5 {
+        for day in 0..5 {
+            if let Some(block) = breakfast_blocks.get(day) {
+                let key = format!("{}-breakfast", format_date(weekday_dates[day]));
+                out.insert(key, merge_continuations(block).join("\n"));
+            }
+        }
+    } else {
+        fill_first_line_per_day(&breakfast_lines, &weekday_dates, "breakfast", &mut out);
+    }
+
+    fill_brunch(
+        &brunch_sections,
+        weekday_date(week_start, Weekday::Sat, first_day),
+        weekday_date(week_start, Weekday::Sun, first_day),
+        &config::config().brunch_fallback_text,
+        &mut out,
+    );
+
+    // Menus without a break row simply produce no break entries.
+    fill_first_line_per_day(&break_lines, &weekday_dates, "break", &mut out);
+
+    let lunch_blocks = split_blocks(&lunch_lines, 5);
+    if lunch_blocks.len() == 5 {
+        for day in 0..5 {
+            if let Some(block) = lunch_blocks.get(day) {
+                let key = format!("{}-lunch", format_date(weekday_dates[day]));
+                out.insert(key, merge_continuations(block).join("\n"));
+            }
+        }
+    } else {
+        fill_first_line_per_day(&lunch_lines, &weekday_dates, "lunch", &mut out);
+    }
+
+    let dinner_blocks = split_blocks(&dinner_lines, 7);
+    if dinner_blocks.len() == 7 {
+        for day in 0..7 {
+            if let Some(block) = dinner_blocks.get(day) {
+                let key = format!("{}-dinner", format_date(all_dates[day]));
+                out.insert(key, merge_continuations(block).join("\n"));
+            }
+        }
+    } else {
+        fill_first_line_per_day(&dinner_lines, &all_dates, "dinner", &mut out);
+    }
+
+    (out, order)
+}
+
+/// Splits the brunch section(s) into Saturday and Sunday blocks. Two explicit
+/// headers give one column each; a single merged weekend header is split like
+/// lunch/dinner columns, and a single block is served on both days. A header
+/// with no content gets `fallback`, or no entry at all when it is empty.
+pub fn fill_brunch(
+    sections: &[Vec<String>],
+    saturday_date: NaiveDate,
+    sunday_date: NaiveDate,
+    fallback: &str,
+    out: &mut HashMap<String, String>,
+) {
+    if sections.is_empty() {
+        return;
+    }
+
+    let blocks: Vec<Vec<String>> = if sections.len() >= 2 {
+        sections
+            .iter()
+            .take(2)
+            .map(|section| {
+                section
+                    .iter()
+                    .map(|line| line.trim())
+                    .filter(|line| !is_junk_line(line, &line.to_lowercase()))
+                    .map(str::to_string)
+                    .collect()
+            })
+            .collect()
+    } else {
+        split_blocks(&sections[0], 2)
+    };
+    let blocks: Vec<String> = blocks
+        .iter()
+        .filter(|block| !block.is_empty())
+        .map(|block| block.join("\n"))
+        .collect();
+
+    let (saturday, sunday) = match blocks.as_slice() {
+        [saturday, sunday, ..] => (saturday.clone(), sunday.clone()),
+        [both] => (both.clone(), both.clone()),
+        [] if fallback.trim().is_empty() => return,
+        [] => (fallback.to_string(), fallback.to_string()),
+    };
+    for (date, meal) in [(saturday_date, saturday), (sunday_date, sunday)] {
+        out.insert(format!("{}-brunch", format_date(date)), meal);
+    }
+}
+
+/// Recognized theme keywords (lowercase) and the name each is reported as.
+pub const THEME_KEYWORDS: [(&str, &str); 9] = [
+    ("burns night", "Burns Night"),
+    ("pancake day", "Pancake Day"),
+    ("shrove tuesday", "Pancake Day"),
+    ("house dinner", "House Dinner"),
+    ("christmas dinner", "Christmas Dinner"),
+    ("christmas lunch", "Christmas Lunch"),
+    ("chinese new year", "Chinese New Year"),
+    ("lunar new year", "Chinese New Year"),
+    ("st george's day", "St George's Day"),
+];
+
+pub fn detect_theme<'a>(items: impl IntoIterator<Item = &'a String>) -> Option<String> {
+    items.into_iter().find_map(|item| {
+        let lower = item.to_lowercase();
+        THEME_KEYWORDS
+            .iter()
+            .find(|(keyword, _)| lower.contains(keyword))
+            .map(|(_, theme)| theme.to_string())
+    })
+}
+
+const DIETARY_MARKERS: [(&str, &str); 8] = [
+    ("v", "vegetarian"),
+    ("ve", "vegan"),
+    ("vg", "vegan"),
+    ("gf", "gluten_free"),
+    ("df", "dairy_free"),
+    ("nf", "nut_free"),
+    ("h", "halal"),
+    ("n", "contains_nuts"),
+];
+
+fn dietary_marker(code: &str) -> Option<&'static str> {
+    let code = code.trim().to_lowercase();
+    DIETARY_MARKERS
+        .iter()
+        .find(|(marker, _)| *marker == code)
+        .map(|(_, label)| *label)
+}
+
+/// Splits the bracketed markers off a dish: "(V)", "(VE, GF)", "(V/GF)" become
+/// dietary tags and "(contains milk, egg)" becomes allergens. Other brackets
+/// stay part of the name.
+pub fn parse_dish(item: &str) -> Dish {
+    let mut name = String::new();
+    let mut dietary: Vec<String> = Vec::new();
+    let mut allergens: Vec<String> = Vec::new();
+
+    let mut rest = item;
+    while let Some(open) = rest.find('(') {
+        let Some(close) = rest[open..].find(')').map(|close| open + close) else {
+            break;
+        };
+        let inner = rest[open + 1..close].trim();
+        let lower = inner.to_lowercase();
+        let codes: Vec<&str> = inner.split([',', '/']).map(str::trim).collect();
+
+        if let Some(list) = lower
+            .strip_prefix("contains")
+            .or_else(|| lower.strip_prefix("allergens:"))
+        {
+            name.push_str(&rest[..open]);
+            allergens.extend(
+                list.split([',', '/'])
+                    .flat_map(|part| part.split(" and "))
+                    .map(|part| part.trim().trim_start_matches(':').trim().to_string())
+                    .filter(|part| !part.is_empty()),
+            );
+        } else if codes.iter().all(|code| dietary_marker(code).is_some()) {
+            name.push_str(&rest[..open]);
+            for label in codes.iter().filter_map(|code| dietary_marker(code)) {
+                if !dietary.iter().any(|seen| seen == label) {
+                    dietary.push(label.to_string());
+                }
+            }
+        } else {
+            name.push_str(&rest[..=close]);
+        }
+        rest = &rest[close + 1..];
+    }
+    name.push_str(rest);
+
+    Dish {
+        name: name.split_whitespace().collect::<Vec<_>>().join(" "),
+        dietary,
+        allergens,
+    }
+}
+
+/// Groups the flat "date-period" map into days, splitting multi-line blocks into items.
+pub fn structure_week(menus: &HashMap<String, String>, week_start: NaiveDate) -> WeekResponse {
+    let days = (0..7)
+        .map(|day| {
+            let date = week_start + chrono::Duration::days(day);
+            let meals: Vec<PeriodMeal> = MealPeriod::ALL
+                .iter()
+                .map(|period| period.as_str())
+                .filter_map(|period| {
+                    let meal = menus.get(&format!("{}-{period}", format_date(date)))?;
+                    let items: Vec<String> = meal
+                        .lines()
+                        .map(str::trim)
+                        .filter(|line| !line.is_empty())
+                        .map(str::to_string)
+                        .collect();
+                    Some(PeriodMeal {
+                        period: period.to_string(),
+                        dishes: items.iter().map(|item| parse_dish(item)).collect(),
+                        items,
+                    })
+                })
+                .collect();
+            let theme = detect_theme(meals.iter().flat_map(|meal| &meal.items));
+            DayMenu {
+                date: format_output_date(date),
+                weekday: date.format("%A").to_string(),
+                meals,
+                theme,
+            }
+        })
+        .collect();
+
+    WeekResponse {
+        week_start: format_output_date(week_start),
+        days,
+        notes: WeekNotes::default(),
+        prev_week: None,
+        next_week: None,
+    }
+}
+
+/// Slices one day out of the resolved week, mapping by weekday when the week was inferred.
+pub fn day_response(week: &ResolvedWeek, date: NaiveDate) -> DayResponse {
+    let mut response = week_response(week);
+    let offset = weekday_offset(date.weekday(), week_start_day()) as usize;
+    let day = response.days.swap_remove(offset);
+
+    DayResponse {
+        date: format_output_date(date),
+        weekday: day.weekday,
+        week_start: response.week_start,
+        meals: day.meals,
+        theme: day.theme,
+        notes: response.notes,
+    }
+}
+
+/// The Mondays either side of `week_start`, flagged by whether a menu is published for them.
+pub fn week_neighbors(
+    week_start: NaiveDate,
+    published: &[NaiveDate],
+) -> (WeekNeighbor, WeekNeighbor) {
+    let neighbor = |offset: i64| {
+        let start = week_start + chrono::Duration::days(offset);
+        WeekNeighbor {
+            week_start: format_output_date(start),
+            published: published.contains(&start),
+        }
+    };
+    (neighbor(-7), neighbor(7))
+}
+
+pub fn week_response(week: &ResolvedWeek) -> WeekResponse {
+    let mut response = structure_week(&week.menus, week.week_start);
+    response.notes = week.notes.clone();
+    response
+}
+
+/// Renders a week as Markdown: one section per period with a row per day.
+pub fn render_week_markdown(week: &WeekResponse) -> String {
+    let mut out = format!("# Menu for week commencing {}\n", week.week_start);
+
+    for period in MealPeriod::ALL.map(MealPeriod::as_str) {
+        let rows: Vec<(&DayMenu, &PeriodMeal)> = week
+            .days
+            .iter()
+            .filter_map(|day| {
+                let meal = day.meals.iter().find(|meal| meal.period == period)?;
+                Some((day, meal))
+            })
+            .collect();
+        if rows.is_empty() {
+            continue;
+        }
+
+        let mut title = period.to_string();
+        title[..1].make_ascii_uppercase();
+        out.push_str(&format!("\n## {title}\n\n| Day | Menu |\n| --- | --- |\n"));
+
+        for (day, meal) in rows {
+            let items: Vec<String> = meal
+                .items
+                .iter()
+                .map(|item| item.replace('|', "\\|"))
+                .collect();
+            let cell = if items.len() == 1 {
+                items[0].clone()
+            } else {
+                items
+                    .iter()
+                    .map(|item| format!("• {item}"))
+                    .collect::<Vec<_>>()
+                    .join("<br>")
+            };
+            out.push_str(&format!("| {} {} | {cell} |\n", day.weekday, day.date));
+        }
+    }
+
+    if !week.notes.notes.is_empty() || week.notes.last_updated.is_some() {
+        out.push_str("\n## Notes\n\n");
+        for note in &week.notes.notes {
+            out.push_str(&format!("- {note}\n"));
+        }
+        if let Some(last_updated) = &week.notes.last_updated {
+            out.push_str(&format!("- Last updated {last_updated}\n"));
+        }
+    }
+
+    out
+}
+
+pub fn week_coverage(menus: &HashMap<String, String>, week_start: NaiveDate) -> CoverageResponse {
+    let mut periods = Vec::new();
+    let mut missing = Vec::new();
+
+    let first_day = week_start_day();
+    for (period, days) in EXPECTED_SLOTS {
+        let period = period.as_str();
+        let mut filled = 0;
+        for day in days {
+            let date = weekday_date(week_start, *day, first_day);
+            let key = format!("{}-{period}", format_date(date));
+            if menus.contains_key(&key) {
+                filled += 1;
+            } else {
+                missing.push(key);
+            }
+        }
+        periods.push(PeriodCoverage {
+            period: period.to_string(),
+            filled,
+            expected: days.len(),
+        });
+    }
+
+    let summary = periods
+        .iter()
+        .map(|p| format!("{} {}/{}", p.period, p.filled, p.expected))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    CoverageResponse {
+        week_start: format_output_date(week_start),
+        summary,
+        periods,
+        missing,
+    }
+}
+
+/// Maximum edit distance for a query term to count as a fuzzy match of a dish word.
+fn max_edits(term: &str) -> usize {
+    match term.chars().count() {
+        0..=3 => 0,
+        4..=6 => 1,
+        _ => 2,
+    }
+}
+
+/// Scores how well every query term matches some word of the meal, or `None` if any term misses.
+pub fn fuzzy_score(query: &str, meal: &str) -> Option<f64> {
+    let words: Vec<String> = meal
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+
+    let mut total = 0.0;
+    let mut terms = 0;
+    for term in query
+        .split_whitespace()
+        .map(str::to_lowercase)
+        .filter(|t| !t.is_empty())
+    {
+        let best = words
+            .iter()
+            .filter(|word| {
+                // Allow abbreviations such as "spag" for "spaghetti".
+                (term.len() >= 3 && word.starts_with(term.as_str()))
+                    || strsim::levenshtein(&term, word) <= max_edits(&term)
+            })
+            .map(|word| strsim::jaro_winkler(&term, word))
+            .fold(None, |acc: Option<f64>, score| {
+                Some(acc.map_or(score, |best| best.max(score)))
+            })?;
+        total += best;
+        terms += 1;
+    }
+
+    if terms == 0 {
+        return None;
+    }
+    Some(total / terms as f64)
+}
+
+pub fn search_index(
+    index: &HashMap<String, String>,
+    query: &str,
+    fuzzy: bool,
+) -> Vec<SearchResult> {
+    let query_lower = query.to_lowercase();
+    let primary = primary_menu_type();
+
+    let mut results: Vec<SearchResult> = index
+        .iter()
+        .filter_map(|(key, meal)| {
+            let score = if fuzzy {
+                fuzzy_score(query, meal)?
+            } else if meal.to_lowercase().contains(&query_lower) {
+                1.0
+            } else {
+                return None;
+            };
+            // Keys are "YYYY-MM-DD-period", prefixed "type:" for other menu
+            // types than the primary one (see `menu_key`).
+            let (menu_type, key) = match key.split_once(':') {
+                Some((menu_type, key)) => (menu_type, key),
+                None => (primary.as_str(), key.as_str()),
+            };
+            let (date, period) = key.split_at_checked(10)?;
+            let date = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                .map(format_output_date)
+                .unwrap_or_else(|_| date.to_string());
+            Some(SearchResult {
+                date,
+                period: period.trim_start_matches('-').to_string(),
+                menu_type: menu_type.to_string(),
+                meal: meal.clone(),
+                score,
+            })
+        })
+        .collect();
+
+    results.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| a.date.cmp(&b.date))
+            .then_with(|| a.period.cmp(&b.period))
+    });
+    results
+}
+
+/// Orders weeks nearest to `today` first and keeps at most `max_weeks` of them.
+pub fn prioritize_weeks(
+    menus: &mut Vec<(String, NaiveDate)>,
+    today: NaiveDate,
+    max_weeks: Option<usize>,
+) {
+    // The week containing today always survives the cap, even on a Sunday
+    // when next week's start is nearer.
+    menus.sort_by_key(|(_, week_start)| {
+        let offset = (today - *week_start).num_days();
+        (!(0..7).contains(&offset), offset.abs())
+    });
+    if let Some(max_weeks) = max_weeks {
+        menus.truncate(max_weeks);
+    }
+}
+
+pub struct IndexReport {
+    pub index: HashMap<String, String>,
+    /// Weeks that produced at least one entry.
+    pub weeks_parsed: usize,
+    /// Links that could not be fetched or extracted, with the error.
+    pub failures: Vec<(String, String)>,
+}
+
+/// Runs the full scrape, continuing past weeks that fail so the caller can judge.
+pub async fn build_index_report() -> anyhow::Result<IndexReport> {
+    let client = build_client()?;
+    let mut menus = resolve_all_menu_weeks(&client).await?;
+    let primary = primary_menu_type();
+
+    // MAX_WEEKS caps how many PDFs are processed; 0 means unbounded.
+    let max_weeks = Some(config::config().max_weeks).filter(|value| *value > 0);
+    prioritize_weeks(&mut menus, today(), max_weeks);
+
+    let mut index = HashMap::new();
+    let mut weeks_parsed = 0;
+    let mut failures = Vec::new();
+    for (link, week_start) in menus {
+        tracing::info!("Processing {link} (week starting {week_start})");
+        let week_menus = match load_week(&client, &link, week_start).await {
+            Ok(week) => week.menus,
+            Err(err) => {
+                tracing::warn!("Failed to process {link}: {err}");
+                failures.push((link, err.to_string()));
+                continue;
+            }
+        };
+        if !week_menus.is_empty() {
+            weeks_parsed += 1;
+        }
+
+        let menu_type = lock_cache().menu_type(&link);
+        for (k, v) in week_menus {
+            let k = menu_key(&menu_type, &k, &primary);
+            tracing::debug!("Storing key: {} -> {}", k, v);
+            index.insert(k, v);
+        }
+    }
+
+    lock_cache().record_index_build();
+
+    tracing::info!("Total entries in index: {}", index.len());
+    tracing::debug!(
+        "Sample keys: {:?}",
+        index.keys().take(5).collect::<Vec<_>>()
+    );
+
+    Ok(IndexReport {
+        index,
+        weeks_parsed,
+        failures,
+    })
+}
+
+pub async fn build_index() -> anyhow::Result<HashMap<String, String>> {
+    let report = build_index_report().await?;
+    if let Some((link, err)) = report.failures.first() {
+        anyhow::bail!("Failed to process {link}: {err}");
+    }
+    Ok(report.index)
+}
+
+/// What a scheduled refresh did, returned to the invoking scheduler.
+#[derive(Debug, Serialize)]
+pub struct RefreshSummary {
+    pub entries: usize,
+    pub weeks_parsed: usize,
+    pub failures: Vec<RefreshFailure>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RefreshFailure {
+    pub link: String,
+    pub error: String,
+}
+
+/// True for the payload EventBridge sends on a schedule rule, as opposed to an
+/// API Gateway request.
+pub fn is_scheduled_event(payload: &Value) -> bool {
+    payload.get("source").and_then(Value::as_str) == Some("aws.events")
+        && payload.get("detail-type").and_then(Value::as_str) == Some("Scheduled Event")
+}
+
+/// Runs the full scrape and writes the index to `store`. An index with no
+/// parsed weeks is not written, so a broken scrape keeps the previous copy.
+pub async fn run_scheduled_refresh(store: &impl MenuStore) -> anyhow::Result<RefreshSummary> {
+    let report = build_index_report().await?;
+    if report.weeks_parsed == 0 {
+        anyhow::bail!(
+            "No weeks were parsed ({} failures); keeping the stored index",
+            report.failures.len()
+        );
+    }
+    store.store(&report.index).await?;
+    tracing::info!(
+        "Stored {} entries from {} weeks",
+        report.index.len(),
+        report.weeks_parsed
+    );
+
+    Ok(RefreshSummary {
+        entries: report.index.len(),
+        weeks_parsed: report.weeks_parsed,
+        failures: report
+            .failures
+            .into_iter()
+            .map(|(link, error)| RefreshFailure { link, error })
+            .collect(),
+    })
+}
+
+pub struct ResolvedWeek {
+    pub week_start: NaiveDate,
+    pub link: String,
+    pub menus: HashMap<String, String>,
+    pub notes: WeekNotes,
+    /// Every week start currently published on the catering page.
+    pub published: Vec<NaiveDate>,
+    /// Served from a cache entry past its TTL while a re-fetch runs.
+    pub stale: bool,
+    pub resolution: WeekResolution,
+}
+
+pub async fn cached_text(client: &Client, url: &str) -> anyhow::Result<String> {
+    // Bind first so the cache lock is released before any await.
+    let cached = lock_cache().text(url);
+    if let Some(text) = cached {
+        return Ok(text);
+    }
+    let text = download_and_extract_text(client, url).await?;
+    lock_cache().insert_text(url.to_string(), text.clone());
+    Ok(text)
+}
+
+/// The weeks a PDF covers: one per section of a multi-week PDF, otherwise the
+/// anchor date, the PDF's own week-commencing header or a date in the filename.
+pub fn pdf_week_starts(link: &str, text: &str, anchor: Option<NaiveDate>) -> Vec<NaiveDate> {
+    let sections = split_week_sections(text);
+    if !sections.is_empty() {
+        return sections
+            .into_iter()
+            .map(|(week_start, _)| week_start)
+            .collect();
+    }
+    anchor
+        .or_else(|| parse_week_commencing_from_pdf_text(text))
+        .or_else(|| parse_week_commencing_from_filename(link))
+        .into_iter()
+        .collect()
+}
+
+/// `pdf_week_starts` for a link, reading the PDF the first time it is seen.
+async fn link_week_starts(
+    client: &Client,
+    link: &str,
+    anchor: Option<NaiveDate>,
+) -> Vec<NaiveDate> {
+    let known = lock_cache().link_weeks(link);
+    if let Some(week_starts) = known {
+        return week_starts;
+    }
+    match cached_text(client, link).await {
+        Ok(text) => {
+            let week_starts = pdf_week_starts(link, &text, anchor);
+            lock_cache().set_link_weeks(link, week_starts.clone());
+            week_starts
+        }
+        // Not remembered, so the PDF is read again on the next resolve.
+        Err(err) => {
+            tracing::warn!("Could not read {link} to find its weeks: {err}");
+            anchor
+                .or_else(|| parse_week_commencing_from_filename(link))
+                .into_iter()
+                .collect()
+        }
+    }
+}
+
+/// The primary menu type's weeks, which requests are answered from.
+pub async fn resolve_menu_weeks(client: &Client) -> anyhow::Result<Vec<(String, NaiveDate)>> {
+    let menus = resolve_all_menu_weeks(client).await?;
+    let primary = primary_menu_type();
+    let cache = lock_cache();
+    Ok(menus
+        .into_iter()
+        .filter(|(link, _)| cache.menu_type(link) == primary)
+        .collect())
+}
+
+/// Resolves every menu link to the week it covers, dropping links with no
+/// detectable week. Each menu type keeps its own link per week.
+pub async fn resolve_all_menu_weeks(client: &Client) -> anyhow::Result<Vec<(String, NaiveDate)>> {
+    let links = fetch_menu_links(client).await?;
+
+    let mut menus = Vec::new();
+    for (link, week_start_opt) in links {
+        // Tables always carry their week; there is no PDF to read.
+        if menu_table_index(&link).is_some() {
+            menus.extend(week_start_opt.map(|week_start| (link, week_start)));
+            continue;
+        }
+
+        let week_starts = link_week_starts(client, &link, week_start_opt).await;
+        if week_starts.is_empty() {
+            tracing::warn!("Skipping {link} - could not parse week start date");
+        }
+        menus.extend(
+            week_starts
+                .into_iter()
+                .map(|week_start| (link.clone(), week_start)),
+        );
+    }
+
+    let (menus, conflicts) = resolve_duplicate_weeks(
+        menus,
+        |url| lock_cache().menu_type(url),
+        |url| {
+            lock_cache()
+                .validators(url)
+                .last_modified
+                .and_then(|value| chrono::DateTime::parse_from_rfc2822(&value).ok())
+        },
+    );
+    for conflict in &conflicts {
+        tracing::warn!(
+            "{} week {} is claimed by {:?}; using {}",
+            conflict.menu_type,
+            conflict.week_start,
+            conflict.links,
+            conflict.chosen
+        );
+    }
+    lock_cache().set_conflicts(conflicts);
+
+    Ok(menus)
+}
+
+/// Keeps one link per menu type and week start. When several links claim a
+/// week, the most recently modified PDF wins if both report `Last-Modified`,
+/// else the later link on the page.
+pub fn resolve_duplicate_weeks<M, F>(
+    menus: Vec<(String, NaiveDate)>,
+    menu_type: M,
+    last_modified: F,
+) -> (Vec<(String, NaiveDate)>, Vec<WeekConflict>)
+where
+    M: Fn(&str) -> String,
+    F: Fn(&str) -> Option<chrono::DateTime<chrono::FixedOffset>>,
+{
+    let mut kept: Vec<(String, NaiveDate)> = Vec::new();
+    let mut claims: BTreeMap<(String, NaiveDate), Vec<String>> = BTreeMap::new();
+    for (link, week_start) in menus {
+        let link_type = menu_type(&link);
+        let links = claims.entry((link_type.clone(), week_start)).or_default();
+        if links.contains(&link) {
+            continue;
+        }
+        links.push(link.clone());
+
+        match kept.iter_mut().find(|(kept_link, kept_start)| {
+            *kept_start == week_start && menu_type(kept_link) == link_type
+        }) {
+            None => kept.push((link, week_start)),
+            Some((kept_link, _)) => {
+                let replace = match (last_modified(kept_link), last_modified(&link)) {
+                    (Some(kept_at), Some(new_at)) => new_at >= kept_at,
+                    _ => true,
+                };
+                if replace {
+                    *kept_link = link;
+                }
+            }
+        }
+    }
+
+    let conflicts = claims
+        .into_iter()
+        .filter(|(_, links)| links.len() > 1)
+        .map(|((link_type, week_start), links)| WeekConflict {
+            week_start: format_date(week_start),
+            chosen: kept
+                .iter()
+                .find(|(link, kept_start)| {
+                    *kept_start == week_start && menu_type(link) == link_type
+                })
+                .map(|(link, _)| link.clone())
+                .unwrap_or_default(),
+            menu_type: link_type,
+            links,
+        })
+        .collect();
+    (kept, conflicts)
+}
+
+fn parse_cached_week(link: &str, text: &str, week_start: NaiveDate) -> CachedWeek {
+    let mut menus = parse_weekly_menu(text, week_start);
+    if menus.is_empty() {
+        if looks_like_single_day_menu(text) {
+            let date = find_menu_date(text).unwrap_or(week_start);
+            menus = parse_single_day_menu(text, date);
+        } else {
+            tracing::warn!(link, "No meals found in weekly menu");
+        }
+    }
+    CachedWeek {
+        link: link.to_string(),
+        menus,
+        notes: parse_week_notes(text),
+        fetched_at: std::time::Instant::now(),
+    }
+}
+
+/// Parses a PDF into one cached week per week-commencing section; text
+/// without sections is parsed whole for each of `week_starts`.
+fn parse_pdf_weeks(
+    link: &str,
+    text: &str,
+    week_starts: &[NaiveDate],
+) -> Vec<(NaiveDate, CachedWeek)> {
+    let sections = split_week_sections(text);
+    if sections.is_empty() {
+        return week_starts
+            .iter()
+            .map(|&week_start| (week_start, parse_cached_week(link, text, week_start)))
+            .collect();
+    }
+
+    let notes = parse_week_notes(text);
+    sections
+        .into_iter()
+        .map(|(section_start, section)| {
+            let week = CachedWeek {
+                notes: notes.clone(),
+                ..parse_cached_week(link, section, section_start)
+            };
+            (section_start, week)
+        })
+        .collect()
+}
+
+/// Caches every week of the PDF under its own start and returns `week_start`'s,
+/// which is empty when the PDF no longer covers that week.
+fn store_pdf_weeks(link: &str, text: &str, week_start: NaiveDate) -> CachedWeek {
+    let mut weeks = parse_pdf_weeks(link, text, &[week_start]);
+    let requested = match weeks.iter().find(|(start, _)| *start == week_start) {
+        Some((_, week)) => week.clone(),
+        None => {
+            tracing::warn!("{link} has no section for week {}", format_date(week_start));
+            let week = CachedWeek {
+                link: link.to_string(),
+                menus: HashMap::new(),
+                notes: parse_week_notes(text),
+                fetched_at: std::time::Instant::now(),
+            };
+            weeks.push((week_start, week.clone()));
+            week
+        }
+    };
+
+    let mut cache = lock_cache();
+    for (start, week) in weeks {
+        cache.insert_week(start, week);
+    }
+    requested
+}
+
+pub async fn load_week(
+    client: &Client,
+    link: &str,
+    week_start: NaiveDate,
+) -> anyhow::Result<CachedWeek> {
+    if let Some(index) = menu_table_index(link) {
+        return load_html_week(client, link, index, week_start).await;
+    }
+
+    let cached = lock_cache().week(week_start, link);
+    if let Some(week) = cached {
+        match freshness_policy().classify(week.fetched_at.elapsed()) {
+            Freshness::Fresh => return Ok(week),
+            Freshness::Stale => {
+                spawn_revalidation(format!("{link}#{}", format_date(week_start)), {
+                    let client = client.clone();
+                    let link = link.to_string();
+                    async move {
+                        revalidate_week(&client, &link, week_start)
+                            .await
+                            .map(|_| ())
+                    }
+                });
+                return Ok(week);
+            }
+            Freshness::Expired => return revalidate_week(client, link, week_start).await,
+        }
+    }
+
+    let text = cached_text(client, link).await?;
+    Ok(store_pdf_weeks(link, &text, week_start))
+}
+
+/// Parses an HTML menu table from the catering page, which carries its own
+/// freshness. The parsed week is reused until the page is re-fetched.
+async fn load_html_week(
+    client: &Client,
+    link: &str,
+    index: usize,
+    week_start: NaiveDate,
+) -> anyhow::Result<CachedWeek> {
+    let page_url = menu_table_page(link);
+    let page = landing_page(client, page_url).await?;
+    let cached = {
+        let cache = lock_cache();
+        let page_age = cache.landing_page_with_age(page_url).map(|(_, age)| age);
+        cache
+            .week(week_start, link)
+            .filter(|week| page_age.is_some_and(|age| week.fetched_at.elapsed() <= age))
+    };
+    if let Some(week) = cached {
+        return Ok(week);
+    }
+
+    let table = extract_menu_tables(&page)
+        .into_iter()
+        .nth(index)
+        .ok_or_else(|| anyhow::anyhow!("Menu table {index} is no longer on the catering page"))?;
+
+    let week = CachedWeek {
+        link: link.to_string(),
+        menus: parse_weekly_menu_html(&table.html, week_start),
+        notes: WeekNotes::default(),
+        fetched_at: std::time::Instant::now(),
+    };
+    lock_cache().insert_week(week_start, week.clone());
+    Ok(week)
+}
+
+/// Conditionally re-downloads a week's PDF and re-parses it, reusing the cached
+/// text when the server answers 304.
+async fn revalidate_week(
+    client: &Client,
+    link: &str,
+    week_start: NaiveDate,
+) -> anyhow::Result<CachedWeek> {
+    let text = match conditional_get(client, link, true).await? {
+        Some(bytes) => {
+            let hash = content_hash(&bytes);
+            let unchanged = {
+                let mut cache = lock_cache();
+                let unchanged = cache.content_hash(link).as_deref() == Some(hash.as_str());
+                if unchanged {
+                    cache.touch_weeks_for_link(link);
+                }
+                unchanged.then(|| cache.week(week_start, link)).flatten()
+            };
+            if let Some(week) = unchanged {
+                return Ok(week);
+            }
+
+            let text = extract_pdf_text(&bytes, link)?;
+            let mut cache = lock_cache();
+            cache.insert_text(link.to_string(), text.clone());
+            cache.set_content_hash(link, hash);
+            text
+        }
+        None => cached_text(client, link).await?,
+    };
+    Ok(store_pdf_weeks(link, &text, week_start))
+}
+
+/// Picks the published (link, week start) serving `date`, inferring relative to `today`.
+pub fn resolve_target(
+    menus: &[(String, NaiveDate)],
+    date: NaiveDate,
+    today: NaiveDate,
+) -> Option<(String, WeekResolution)> {
+    let week_starts: Vec<NaiveDate> = menus.iter().map(|(_, week_start)| *week_start).collect();
+    let resolution = choose_inferred_week_start(
+        &week_starts,
+        date,
+        today,
+        config::config().max_inference_weeks,
+    )?;
+    menus
+        .iter()
+        .find(|(_, week_start)| *week_start == resolution.week_start())
+        .map(|(link, _)| (link.clone(), resolution))
+}
+
+pub async fn fetch_week_for_date(date: NaiveDate) -> anyhow::Result<Option<ResolvedWeek>> {
+    fetch_week_for_date_on(date, today()).await
+}
+
+/// Like `fetch_week_for_date`, with "today" pinned for deterministic inference.
+pub async fn fetch_week_for_date_on(
+    date: NaiveDate,
+    today: NaiveDate,
+) -> anyhow::Result<Option<ResolvedWeek>> {
+    let client = build_client()?;
+    let menus = resolve_menu_weeks(&client).await?;
+    ensure_published(&menus, date)?;
+
+    let (link, resolution) = match resolve_target(&menus, date, today) {
+        Some(value) => value,
+        None => return Ok(None),
+    };
+    let week_start = resolution.week_start();
+
+    let week = load_week(&client, &link, week_start).await?;
+    Ok(Some(ResolvedWeek {
+        week_start,
+        link,
+        menus: week.menus,
+        notes: week.notes,
+        published: menus.iter().map(|(_, week_start)| *week_start).collect(),
+        stale: freshness_policy().classify(week.fetched_at.elapsed()) != Freshness::Fresh,
+        resolution,
+    }))
+}
+
+/// The latest published week starting before `week_start`.
+pub fn previous_published_week(
+    menus: &[(String, NaiveDate)],
+    week_start: NaiveDate,
+) -> Option<&(String, NaiveDate)> {
+    menus
+        .iter()
+        .filter(|(_, candidate)| *candidate < week_start)
+        .max_by_key(|(_, candidate)| *candidate)
+}
+
+/// Looks up `date`'s weekday and `period` in the week before `week`, for
+/// `?fallback=prev` when `week` lacks the period.
+async fn previous_week_meal(
+    week: &ResolvedWeek,
+    date: NaiveDate,
+    period: &str,
+) -> anyhow::Result<Option<(String, ResolvedWeek)>> {
+    let client = build_client()?;
+    let menus = resolve_menu_weeks(&client).await?;
+    let Some((link, previous_start)) = previous_published_week(&menus, week.week_start).cloned()
+    else {
+        return Ok(None);
+    };
+
+    let previous = load_week(&client, &link, previous_start).await?;
+    let Some(meal) = lookup_meal(&previous.menus, previous_start, date, period) else {
+        return Ok(None);
+    };
+    Ok(Some((
+        meal,
+        ResolvedWeek {
+            week_start: previous_start,
+            link,
+            stale: freshness_policy().classify(previous.fetched_at.elapsed()) != Freshness::Fresh,
+            menus: previous.menus,
+            notes: previous.notes,
+            published: week.published.clone(),
+            resolution: WeekResolution::PreviousWeekFallback(previous_start),
+        },
+    )))
+}
+
+/// Re-validates the landing page and every cached PDF, re-parsing only what changed.
+pub async fn refresh_cache() -> anyhow::Result<bool> {
+    let client = build_client()?;
+
+    let mut changed = false;
+    for page_url in catering_page_urls() {
+        let page_before = lock_cache().landing_page(&page_url);
+        let page = match revalidate_landing_page(&client, &page_url).await {
+            Ok(page) => page,
+            Err(err) => {
+                tracing::warn!("Skipping catering page {page_url}: {err}");
+                continue;
+            }
+        };
+        changed |= page_before.as_deref() != Some(page.as_str());
+    }
+
+    // Weeks outlive their evicted source text, so walk the weeks' links.
+    let urls = lock_cache().week_links();
+    for url in urls {
+        // Table weeks live on the landing pages revalidated above.
+        if menu_table_index(&url).is_some() {
+            continue;
+        }
+        let bytes = match conditional_get(&client, &url, true).await {
+            Ok(Some(bytes)) => bytes,
+            Ok(None) => {
+                lock_cache().touch_weeks_for_link(&url);
+                continue;
+            }
+            Err(err) => {
+                tracing::warn!("Refresh of {url} failed: {err}");
+                continue;
+            }
+        };
+        let hash = content_hash(&bytes);
+        {
+            let mut cache = lock_cache();
+            if cache.content_hash(&url).as_deref() == Some(hash.as_str()) {
+                cache.touch_weeks_for_link(&url);
+                continue;
+            }
+        }
+        let text = match extract_pdf_text(&bytes, &url) {
+            Ok(text) => text,
+            Err(err) => {
+                tracing::warn!("Refresh of {url} failed: {err}");
+                continue;
+            }
+        };
+
+        // Scoped so the cache lock is released before the next request is awaited.
+        {
+            let mut cache = lock_cache();
+            cache.set_content_hash(&url, hash);
+            if cache.text(&url).as_deref() == Some(text.as_str()) {
+                cache.touch_weeks_for_link(&url);
+                continue;
+            }
+            tracing::info!("Menu changed: {url}");
+            let week_starts = cache.weeks_for_link(&url);
+            let anchor = week_starts.iter().min().copied();
+            cache.set_link_weeks(&url, pdf_week_starts(&url, &text, anchor));
+            for (week_start, week) in parse_pdf_weeks(&url, &text, &week_starts) {
+                cache.insert_week(week_start, week);
+            }
+            cache.insert_text(url, text);
+        }
+        changed = true;
+    }
+
+    lock_cache().record_refresh(changed);
+    Ok(changed)
+}
+
+/// Periodically runs `refresh_cache`, starting after a random delay within the
+/// interval so multiple instances don't poll the school site in lockstep.
+pub async fn run_refresher(interval: std::time::Duration) {
+    let jitter = rand::random_range(0..interval.as_millis().max(1) as u64);
+    tokio::time::sleep(std::time::Duration::from_millis(jitter)).await;
+
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        ticker.tick().await;
+        match refresh_cache().await {
+            Ok(changed) => tracing::info!("Refresh complete (changed: {changed})"),
+            Err(err) => tracing::warn!("Refresh failed: {err}"),
+        }
+    }
+}
+
+/// Warms the cache with the current and next week, the ones most likely to be queried.
+pub async fn preload_current_weeks() -> anyhow::Result<Vec<NaiveDate>> {
+    let client = build_client()?;
+    let menus = resolve_menu_weeks(&client).await?;
+
+    let today = today();
+    let mut loaded = Vec::new();
+    for date in [today, today + chrono::Duration::days(7)] {
+        let Some((link, resolution)) = resolve_target(&menus, date, today) else {
+            continue;
+        };
+        let week_start = resolution.week_start();
+        if loaded.contains(&week_start) {
+            continue;
+        }
+        if let Err(err) = load_week(&client, &link, week_start).await {
+            tracing::warn!("Failed to preload {link}: {err}");
+            continue;
+        }
+        loaded.push(week_start);
+    }
+
+    Ok(loaded)
+}
+
+/// Explains why a period is never served on the given date, e.g. lunch at weekends.
+pub fn not_served_reason(period: &str, date: NaiveDate) -> Option<&'static str> {
+    let weekend = WEEKEND.contains(&date.weekday());
+    if period == "lunch" && weekend {
+        return Some("Lunch is not served at weekends; try brunch instead.");
+    }
+    None
+}
+
+pub fn lookup_meal(
+    menus: &HashMap<String, String>,
+    week_start: NaiveDate,
+    date: NaiveDate,
+    period: &str,
+) -> Option<String> {
+    let period_key = period.to_lowercase();
+    let key = format!("{}-{}", format_date(date), period_key);
+
+    if let Some(meal) = menus.get(&key) {
+        return Some(meal.clone());
+    }
+
+    // If we inferred a nearby week, map by weekday within that inferred week.
+    let mapped_date = weekday_date(week_start, date.weekday(), week_start_day());
+    let mapped_key = format!("{}-{}", format_date(mapped_date), period_key);
+    menus.get(&mapped_key).cloned()
+}
+
+pub async fn fetch_meal_for_date(date: NaiveDate, period: &str) -> anyhow::Result<Option<String>> {
+    fetch_meal_for_date_on(date, period, today()).await
+}
+
+pub async fn fetch_meal_for_date_on(
+    date: NaiveDate,
+    period: &str,
+    today: NaiveDate,
+) -> anyhow::Result<Option<String>> {
+    let week = match fetch_week_for_date_on(date, today).await? {
+        Some(value) => value,
+        None => return Ok(None),
+    };
+    Ok(lookup_meal(&week.menus, week.week_start, date, period))
+}
+
+/// The primary menu type's weeks in a stored index, as `(key, week_start)`
+/// pairs like `resolve_menu_weeks` returns. Other menu types' prefixed keys
+/// are left out.
+pub fn stored_index_weeks(index: &store::Index) -> Vec<(String, NaiveDate)> {
+    let first_day = week_start_day();
+    let mut week_starts: Vec<NaiveDate> = index
+        .keys()
+        .filter(|key| !key.contains(':'))
+        .filter_map(|key| NaiveDate::parse_from_str(key.get(..10)?, "%Y-%m-%d").ok())
+        .map(|date| date - chrono::Duration::days(weekday_offset(date.weekday(), first_day)))
+        .collect();
+    week_starts.sort();
+    week_starts.dedup();
+    week_starts
+        .into_iter()
+        .map(|week_start| (format!("index:{}", format_date(week_start)), week_start))
+        .collect()
+}
+
+/// Answers a meal from the stored index with the same week inference,
+/// pending-publication window and `fallback=prev` handling as a live lookup,
+/// so a miss is a miss rather than a reason to scrape.
+pub fn stored_meal_lookup(
+    index: &store::Index,
+    date: NaiveDate,
+    period: &str,
+    fallback_prev: bool,
+    today: NaiveDate,
+) -> Result<Option<String>, CateringError> {
+    let menus = stored_index_weeks(index);
+    ensure_published(&menus, date)?;
+    let Some((_, resolution)) = resolve_target(&menus, date, today) else {
+        return Ok(None);
+    };
+    let week_start = resolution.week_start();
+    let meal = lookup_meal(index, week_start, date, period);
+    if meal.is_some() || !fallback_prev {
+        return Ok(meal);
+    }
+    Ok(previous_published_week(&menus, week_start)
+        .and_then(|(_, previous_start)| lookup_meal(index, *previous_start, date, period)))
+}
+
+pub async fn explain_meal_for_date(
+    date: NaiveDate,
+    period: &str,
+) -> anyhow::Result<Option<MealExplanation>> {
+    let client = build_client()?;
+    let menus = resolve_menu_weeks(&client).await?;
+
+    ensure_published(&menus, date)?;
+
+    let today = today();
+    let (link, resolution) = match resolve_target(&menus, date, today) {
+        Some(value) => value,
+        None => return Ok(None),
+    };
+    let week_start = resolution.week_start();
+    let exact = matches!(resolution, WeekResolution::Exact(_));
+    let week_menus = load_week(&client, &link, week_start).await?.menus;
+
+    let mut candidates = Vec::new();
+    if !exact {
+        for offset in [-7, 7] {
+            let candidate_start = week_start + chrono::Duration::days(offset);
+            let Some((candidate_link, _)) = menus
+                .iter()
+                .find(|(_, candidate)| *candidate == candidate_start)
+            else {
+                continue;
+            };
+            let candidate_menus = load_week(&client, candidate_link, candidate_start)
+                .await?
+                .menus;
+            candidates.push(CandidateMeal {
+                week_start: format_output_date(candidate_start),
+                meal: lookup_meal(&candidate_menus, candidate_start, date, period),
+            });
+        }
+    }
+
+    Ok(Some(MealExplanation {
+        date: format_output_date(date),
+        period: period.to_string(),
+        week_start: format_output_date(week_start),
+        resolution: resolution.as_str(),
+        meal: lookup_meal(&week_menus, week_start, date, period),
+        candidates,
+    }))
+}
+
+pub fn parse_payload<T>(event: &LambdaEvent<Value>, key: &str) -> Option<T>
+where
+    T: for<'de> serde::Deserialize<'de>,
+{
+    event
+        .payload
+        .get(key)
+        .or_else(|| {
+            event
+                .payload
+                .get("queryStringParameters")
                 .and_then(|qs| qs.get(key))
         })
-        .or_else(|| {
-            event
-                .payload
-                .get("pathParameters")
-                .and_then(|pp| pp.get(key))
+        .or_else(|| {
+            event
+                .payload
+                .get("pathParameters")
+                .and_then(|pp| pp.get(key))
+        })
+        .and_then(|v| {
+            // If it's a string, try to deserialize it directly to the target type
+            if let Some(s) = v.as_str() {
+                // First try to deserialize the string directly to T
+                if let Ok(result) = serde_json::from_str::<T>(s) {
+                    return Some(result);
+                }
+                // If that fails, try wrapping it in quotes and deserializing
+                let quoted = format!("\"{}\"", s);
+                if let Ok(result) = serde_json::from_str::<T>(&quoted) {
+                    return Some(result);
+                }
+            }
+            serde_json::from_value(v.clone()).ok()
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_pdf_text_empty_bytes() {
+        let err = extract_pdf_text(&[], "https://www.cranbrookschool.co.uk/menu.pdf").unwrap_err();
+        assert!(matches!(err, CateringError::EmptyPdf { ref url } if url.ends_with("menu.pdf")));
+    }
+
+    #[test]
+    fn test_resolve_date_format() {
+        let date = NaiveDate::from_ymd_opt(2026, 1, 26).unwrap();
+        let render = |raw: &str| date.format(&resolve_date_format(raw)).to_string();
+
+        assert_eq!(render(""), "2026-01-26");
+        assert_eq!(render("ISO"), "2026-01-26");
+        assert_eq!(render("dmy"), "26/01/2026");
+        assert_eq!(render("%A %e %B"), "Monday 26 January");
+        assert_eq!(render("%Q"), "2026-01-26");
+    }
+
+    #[test]
+    fn test_month_from_str() {
+        let cases = [
+            ("January", "Jan", 1),
+            ("February", "Feb", 2),
+            ("March", "Mar", 3),
+            ("April", "Apr", 4),
+            ("May", "May", 5),
+            ("June", "Jun", 6),
+            ("July", "Jul", 7),
+            ("August", "Aug", 8),
+            ("September", "Sept", 9),
+            ("October", "Oct", 10),
+            ("November", "Nov", 11),
+            ("December", "Dec", 12),
+        ];
+        for (full, abbrev, number) in cases {
+            assert_eq!(month_from_str(full), Some(number), "{full}");
+            assert_eq!(month_from_str(&full.to_uppercase()), Some(number), "{full}");
+            assert_eq!(month_from_str(abbrev), Some(number), "{abbrev}");
+            assert_eq!(
+                month_from_str(&format!("{abbrev}.")),
+                Some(number),
+                "{abbrev}."
+            );
+        }
+        assert_eq!(month_from_str("Sep"), Some(9));
+        assert_eq!(month_from_str("Janu"), None);
+        assert_eq!(month_from_str(""), None);
+    }
+
+    #[test]
+    fn test_parse_week_commencing_from_filename() {
+        let date = NaiveDate::from_ymd_opt(2026, 1, 26);
+        let cases = [
+            "https://www.cranbrookschool.co.uk/wp-content/uploads/2026/01/Week-Menu-26-01-2026.pdf",
+            "https://www.cranbrookschool.co.uk/uploads/menu_2026-01-26.pdf",
+            "https://www.cranbrookschool.co.uk/uploads/Menu-26Jan2026.pdf",
+            "https://www.cranbrookschool.co.uk/uploads/Menu-26th-January-2026.pdf?ver=3",
+            "https://www.cranbrookschool.co.uk/uploads/Menu%2026%20January%202026.pdf",
+        ];
+        for url in cases {
+            assert_eq!(parse_week_commencing_from_filename(url), date, "{url}");
+        }
+
+        assert_eq!(
+            parse_week_commencing_from_filename(
+                "https://www.cranbrookschool.co.uk/wp-content/uploads/2026/01/Catering-Menu.pdf"
+            ),
+            None
+        );
+        assert_eq!(
+            parse_week_commencing_from_filename(
+                "https://www.cranbrookschool.co.uk/Menu-31-02-2026.pdf"
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_preview_menu_reports_week_source_and_coverage() {
+        let text = "WEEK COMMENCING MONDAY 26TH JANUARY 2026\n\
+            Lunch Lunch Lunch Lunch Lunch\n\
+            Soup\n \
+            Pasta bake\n";
+        let preview = preview_menu("https://www.cranbrookschool.co.uk/menu.pdf", text);
+
+        assert_eq!(preview.week_start.as_deref(), Some("2026-01-26"));
+        assert_eq!(preview.diagnostics.week_source, Some("pdf"));
+        assert_eq!(preview.menus["2026-01-26-lunch"], "Soup");
+        let coverage = preview.diagnostics.coverage.unwrap();
+        assert!(coverage.missing.contains(&"2026-01-28-lunch".to_string()));
+
+        let undated = preview_menu("https://www.cranbrookschool.co.uk/menu.pdf", "Soup");
+        assert!(undated.week_start.is_none());
+        assert!(undated.menus.is_empty());
+        assert!(undated.diagnostics.coverage.is_none());
+    }
+
+    #[test]
+    fn test_parse_week_commencing_abbreviated_month() {
+        assert_eq!(
+            parse_week_commencing("Menu for w/c Monday 2nd Feb 2026"),
+            NaiveDate::from_ymd_opt(2026, 2, 2)
+        );
+        assert_eq!(
+            parse_week_commencing_from_pdf_text("WEEK COMMENCING MONDAY 7TH SEPT 2026"),
+            NaiveDate::from_ymd_opt(2026, 9, 7)
+        );
+    }
+
+    #[test]
+    fn test_parse_periods() {
+        assert_eq!(parse_periods("Lunch"), Ok(vec![MealPeriod::Lunch]));
+        assert_eq!(
+            parse_periods("lunch,dinner"),
+            Ok(vec![MealPeriod::Lunch, MealPeriod::Dinner])
+        );
+        assert_eq!(
+            parse_periods("breakfast/ lunch / lunch"),
+            Ok(vec![MealPeriod::Breakfast, MealPeriod::Lunch])
+        );
+        assert_eq!(
+            parse_periods("lunch,tea,supper"),
+            Err(vec!["tea".to_string(), "supper".to_string()])
+        );
+        assert!(parse_periods(" , ").is_err());
+    }
+
+    #[test]
+    fn test_parse_weekly_menu_single_breakfast_header() {
+        let week_start = NaiveDate::from_ymd_opt(2026, 1, 26).unwrap();
+        let text = "Breakfast\n\
+            Porridge\n\
+            Pancakes\n\
+            Full English\n\
+            Waffles\n\
+            Croissants\n\
+            Lunch 5\n\
+            Soup\n";
+
+        let menus =
+            parse_weekly_menu_with(text, week_start, &SectionKeywords::default(), Weekday::Mon);
+
+        assert_eq!(menus["2026-01-26-breakfast"], "Porridge");
+        assert_eq!(menus["2026-01-30-breakfast"], "Croissants");
+        assert_eq!(menus["2026-01-26-lunch"], "Soup");
+    }
+
+    #[test]
+    fn test_parse_weekly_menu_multi_item_breakfast() {
+        let week_start = NaiveDate::from_ymd_opt(2026, 1, 26).unwrap();
+        let text = "Breakfast Breakfast Breakfast Breakfast Breakfast\n \
+            Porridge\n\
+            Fresh fruit\n \
+            Pancakes\n\
+            Yoghurt\n \
+            Full English\n \
+            Waffles\n \
+            Croissants\n\
+            Lunch Lunch Lunch Lunch Lunch\n\
+            Soup\n";
+
+        let menus =
+            parse_weekly_menu_with(text, week_start, &SectionKeywords::default(), Weekday::Mon);
+
+        assert_eq!(menus["2026-01-26-breakfast"], "Porridge\nFresh fruit");
+        assert_eq!(menus["2026-01-27-breakfast"], "Pancakes\nYoghurt");
+        assert_eq!(menus["2026-01-28-breakfast"], "Full English");
+        assert_eq!(menus["2026-01-30-breakfast"], "Croissants");
+    }
+
+    #[test]
+    fn test_is_section_header() {
+        let keywords = SectionKeywords::default();
+        assert!(is_section_header("breakfast", &keywords.breakfast));
+        assert!(is_section_header(
+            "breakfast: mon tue wed thu fri",
+            &keywords.breakfast
+        ));
+        assert!(is_section_header("dinner 7", &keywords.dinner));
+        assert!(is_section_header(
+            "menu lunch lunch lunch lunch lunch",
+            &keywords.lunch
+        ));
+        assert!(!is_section_header("breakfast burrito", &keywords.breakfast));
+        assert!(!is_section_header("dinner rolls", &keywords.dinner));
+    }
+
+    #[test]
+    fn test_fill_brunch_fallback() {
+        let saturday = NaiveDate::from_ymd_opt(2026, 1, 31).unwrap();
+        let sunday = NaiveDate::from_ymd_opt(2026, 2, 1).unwrap();
+        let empty_header = vec![vec!["".to_string()]];
+
+        let mut out = HashMap::new();
+        fill_brunch(&empty_header, saturday, sunday, "", &mut out);
+        assert!(out.is_empty());
+
+        fill_brunch(&empty_header, saturday, sunday, "Brunch buffet", &mut out);
+        assert_eq!(out["2026-01-31-brunch"], "Brunch buffet");
+        assert_eq!(out["2026-02-01-brunch"], "Brunch buffet");
+
+        // Parsed content always wins over the fallback.
+        let mut out = HashMap::new();
+        let parsed = vec![vec!["Hot buffet".to_string()]];
+        fill_brunch(&parsed, saturday, sunday, "Brunch buffet", &mut out);
+        assert_eq!(out["2026-01-31-brunch"], "Hot buffet");
+    }
+
+    #[test]
+    fn test_parse_weekly_menu_html_table() {
+        let page = r#"<html><body>
+            <h2>Menu for w/c Monday 26th January 2026</h2>
+            <table>
+              <tr><th></th><th>Monday</th><th>Tuesday</th><th>Wednesday</th><th>Thursday</th><th>Friday</th></tr>
+              <tr><td>Breakfast</td><td>Porridge</td><td>Pancakes</td><td></td><td>Waffles</td><td>Toast</td></tr>
+              <tr><td>Lunch</td><td>Soup<br>Bread</td><td>Pasta</td><td>Curry</td><td>Fish</td><td>Pie</td></tr>
+              <tr><td>Notes</td><td>Ignored</td></tr>
+            </table>
+            <table><tr><td>Opening hours</td></tr></table>
+        </body></html>"#;
+
+        let tables = extract_menu_tables(page);
+        assert_eq!(tables.len(), 1);
+        let week_start = tables[0].week_start.unwrap();
+        assert_eq!(week_start, NaiveDate::from_ymd_opt(2026, 1, 26).unwrap());
+
+        let menus = parse_weekly_menu_html(&tables[0].html, week_start);
+        assert_eq!(menus["2026-01-26-breakfast"], "Porridge");
+        assert_eq!(menus["2026-01-26-lunch"], "Soup\nBread");
+        assert_eq!(menus["2026-01-30-lunch"], "Pie");
+        assert!(!menus.contains_key("2026-01-28-breakfast"));
+        assert_eq!(menus.len(), 9);
+
+        let link = menu_table_link(CATERING_PAGE_URL, 0);
+        assert_eq!(menu_table_index(&link), Some(0));
+        assert_eq!(menu_table_index(CATERING_PAGE_URL), None);
+    }
+
+    #[test]
+    fn test_section_order_diagnostics() {
+        let week_start = NaiveDate::from_ymd_opt(2026, 1, 26).unwrap();
+        let keywords = SectionKeywords::default();
+
+        let in_order = "Breakfast\nPorridge\nLunch 5\nSoup\nDinner 7\nCurry\n";
+        let (_, diagnostics) =
+            parse_weekly_menu_with_diagnostics(in_order, week_start, &keywords, Weekday::Mon);
+        assert_eq!(
+            diagnostics.section_order,
+            vec![vec!["breakfast", "lunch", "dinner"]]
+        );
+        assert!(diagnostics.section_order_ok);
+
+        let reordered = "Lunch 5\nSoup\nBreakfast\nPorridge\n";
+        let (menus, diagnostics) =
+            parse_weekly_menu_with_diagnostics(reordered, week_start, &keywords, Weekday::Mon);
+        assert_eq!(diagnostics.section_order, vec![vec!["lunch", "breakfast"]]);
+        assert!(!diagnostics.section_order_ok);
+        assert_eq!(menus["2026-01-26-breakfast"], "Porridge");
+    }
+
+    #[test]
+    fn test_section_order_matches() {
+        use MealPeriod::*;
+        let expected = MealPeriod::ALL;
+        assert!(section_order_matches(
+            &[Breakfast, Lunch, Dinner],
+            &expected
+        ));
+        assert!(section_order_matches(&[], &expected));
+        assert!(!section_order_matches(&[Dinner, Lunch], &expected));
+        assert!(!section_order_matches(&[Lunch, Dinner, Lunch], &expected));
+    }
+
+    #[test]
+    fn test_parse_weekly_menu_morning_break_row() {
+        let week_start = NaiveDate::from_ymd_opt(2026, 1, 26).unwrap();
+        let text = "Breakfast Breakfast Breakfast Breakfast Breakfast\n\
+            Porridge\n\
+            Morning Break\n\
+            Flapjack\n\
+            Fruit\n\
+            Cookies\n\
+            Muffins\n\
+            Toast\n\
+            Lunch Lunch Lunch Lunch Lunch\n\
+            Soup\n";
+
+        let menus =
+            parse_weekly_menu_with(text, week_start, &SectionKeywords::default(), Weekday::Mon);
+
+        assert_eq!(menus["2026-01-26-breakfast"], "Porridge");
+        assert_eq!(menus["2026-01-26-break"], "Flapjack");
+        assert_eq!(menus["2026-01-30-break"], "Toast");
+        assert_eq!(menus["2026-01-26-lunch"], "Soup");
+
+        // Weeks without a break row just have no break entries.
+        let plain = parse_weekly_menu_with(
+            "Lunch Lunch Lunch Lunch Lunch\nSoup\n",
+            week_start,
+            &SectionKeywords::default(),
+            Weekday::Mon,
+        );
+        assert!(!plain.keys().any(|key| key.ends_with("-break")));
+
+        // "Break" mid-line is a dish or a note, not a section header.
+        let text = "Lunch Lunch Lunch Lunch Lunch\n\
+            Soup\n\
+            Kit Kat break bars\n\
+            No service over Half Term Break\n";
+        let menus =
+            parse_weekly_menu_with(text, week_start, &SectionKeywords::default(), Weekday::Mon);
+        assert!(!menus.keys().any(|key| key.ends_with("-break")));
+        assert!(menus.contains_key("2026-01-26-lunch"));
+    }
+
+    #[test]
+    fn test_parse_periods_break_aliases() {
+        assert_eq!(parse_periods("break"), Ok(vec![MealPeriod::Break]));
+        assert_eq!(parse_periods("Morning Break"), Ok(vec![MealPeriod::Break]));
+        assert_eq!(
+            parse_periods("elevenses,lunch"),
+            Ok(vec![MealPeriod::Break, MealPeriod::Lunch])
+        );
+    }
+
+    #[test]
+    fn test_not_served_reason_weekend_lunch() {
+        let saturday = NaiveDate::from_ymd_opt(2026, 1, 31).unwrap();
+        let sunday = NaiveDate::from_ymd_opt(2026, 2, 1).unwrap();
+        let friday = NaiveDate::from_ymd_opt(2026, 1, 30).unwrap();
+
+        assert!(not_served_reason("lunch", saturday).is_some());
+        assert!(not_served_reason("lunch", sunday).is_some());
+        assert!(not_served_reason("lunch", friday).is_none());
+        assert!(not_served_reason("dinner", saturday).is_none());
+        assert!(not_served_reason("dinner", sunday).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_get_meal_saturday_lunch_is_not_served() {
+        let query = MealQuery::try_from(QueryParams {
+            date: "2026-01-31".to_string(),
+            period: "Lunch".to_string(),
+            explain: false,
+            fallback: None,
+        })
+        .unwrap();
+        let response = get_meal(query).await.into_response();
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["status"], "not_served");
+    }
+
+    #[test]
+    fn test_is_scheduled_event() {
+        let scheduled = serde_json::json!({
+            "version": "0",
+            "source": "aws.events",
+            "detail-type": "Scheduled Event",
+            "resources": ["arn:aws:events:eu-west-2:123456789012:rule/refresh"],
+            "detail": {},
+        });
+        assert!(is_scheduled_event(&scheduled));
+
+        let request = serde_json::json!({
+            "queryStringParameters": { "date": "2026-01-26", "period": "lunch" },
+        });
+        assert!(!is_scheduled_event(&request));
+    }
+
+    #[tokio::test]
+    async fn test_get_meal_trims_params() {
+        let query = MealQuery::try_from(QueryParams {
+            date: " 2026-01-31 ".to_string(),
+            period: " LUNCH/ ".to_string(),
+            explain: false,
+            fallback: None,
+        })
+        .unwrap();
+        let response = get_meal(query).await.into_response();
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["status"], "not_served");
+        assert_eq!(json["period"], "lunch");
+        assert_eq!(json["date"], "2026-01-31");
+    }
+
+    #[test]
+    fn test_try_parse_date_param_failure_modes() {
+        assert_eq!(try_parse_date_param("26-01"), Err(DateParamError::Format));
+        assert_eq!(
+            try_parse_date_param("-2026-01-26"),
+            Err(DateParamError::Format)
+        );
+        assert_eq!(
+            try_parse_date_param("2026--01--26"),
+            Err(DateParamError::Format)
+        );
+        assert_eq!(
+            try_parse_date_param("2026-Jan-26"),
+            Err(DateParamError::Format)
+        );
+        assert_eq!(
+            try_parse_date_param("1999-01-26"),
+            Err(DateParamError::Year(1999))
+        );
+        assert_eq!(
+            try_parse_date_param("20260-01-26"),
+            Err(DateParamError::Year(20260))
+        );
+        assert_eq!(
+            try_parse_date_param("2026-13-01"),
+            Err(DateParamError::Month(13))
+        );
+        assert_eq!(
+            try_parse_date_param("2026-00-01"),
+            Err(DateParamError::Month(0))
+        );
+        assert_eq!(
+            try_parse_date_param("2026-01-32"),
+            Err(DateParamError::Day(32))
+        );
+        assert_eq!(
+            try_parse_date_param("2026-02-30"),
+            Err(DateParamError::NotInCalendar {
+                year: 2026,
+                month: 2,
+                day: 30
+            })
+        );
+        assert_eq!(
+            DateParamError::Month(13).to_string(),
+            "Invalid date: month must be 1–12 (got 13)."
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_meal_reports_bad_month() {
+        use tower::ServiceExt;
+
+        let request = axum::http::Request::builder()
+            .uri("/meal?date=2026-13-01&period=lunch")
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let response = build_router(config::config(), CorsLayer::new())
+            .oneshot(request)
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["errors"][0]["field"], "date");
+        assert_eq!(
+            json["errors"][0]["message"],
+            "Invalid date: month must be 1–12 (got 13)."
+        );
+        assert!(json["request_id"].is_string());
+    }
+
+    #[test]
+    fn test_meal_query_reports_every_invalid_field() {
+        let rejection = MealQuery::try_from(QueryParams {
+            date: "2026-02-30".to_string(),
+            period: "lunch,tea".to_string(),
+            explain: false,
+            fallback: Some("next".to_string()),
+        })
+        .unwrap_err();
+
+        let fields: Vec<_> = rejection.errors.iter().map(|error| error.field).collect();
+        assert_eq!(fields, ["date", "period", "fallback"]);
+        assert_eq!(
+            rejection.errors[1].message,
+            "Unrecognized period(s): tea. Use breakfast, break, brunch, lunch or dinner."
+        );
+
+        let query = MealQuery::try_from(QueryParams {
+            date: "2026/01/26".to_string(),
+            period: "lunch,dinner".to_string(),
+            explain: true,
+            fallback: Some("PREV".to_string()),
+        })
+        .unwrap();
+        assert_eq!(
+            query,
+            MealQuery {
+                date: NaiveDate::from_ymd_opt(2026, 1, 26).unwrap(),
+                periods: vec![MealPeriod::Lunch, MealPeriod::Dinner],
+                explain: true,
+                fallback_prev: true,
+            }
+        );
+    }
+
+    #[test]
+    fn test_params_tolerate_whitespace_and_case() {
+        let date = NaiveDate::from_ymd_opt(2026, 1, 26);
+        assert_eq!(parse_date_param(" 2026-01-26 "), date);
+        assert_eq!(parse_date_param("\t2026/01/26/\n"), date);
+        assert_eq!(" Lunch ".parse::<MealPeriod>(), Ok(MealPeriod::Lunch));
+        assert_eq!("dInNeR\n".parse::<MealPeriod>(), Ok(MealPeriod::Dinner));
+        assert_eq!(
+            " Morning Break ".parse::<MealPeriod>(),
+            Ok(MealPeriod::Break)
+        );
+    }
+
+    #[test]
+    fn test_parse_week_notes() {
+        let text = "Week Commencing Monday 26th January 2026\n\
+            Lunch Lunch Lunch\n\
+            Chicken pie\n\
+            All dishes   subject to availability\n\
+            Please inform a member of staff of any allergies\n\
+            Last updated 19/01/2026\n\
+            Fish and chips\n";
+
+        let notes = parse_week_notes(text);
+
+        assert_eq!(
+            notes.notes,
+            vec![
+                "All dishes subject to availability".to_string(),
+                "Please inform a member of staff of any allergies".to_string(),
+            ]
+        );
+        assert_eq!(notes.last_updated.as_deref(), Some("2026-01-19"));
+        assert_eq!(
+            parse_last_updated("Updated: 5th February 2026"),
+            NaiveDate::from_ymd_opt(2026, 2, 5)
+        );
+        assert!(is_junk_line(
+            "All dishes subject to availability",
+            "all dishes subject to availability"
+        ));
+        assert!(!is_junk_line("Chicken pie", "chicken pie"));
+    }
+
+    fn published(week_starts: &[(u32, u32)]) -> Vec<(String, NaiveDate)> {
+        week_starts
+            .iter()
+            .map(|(month, day)| {
+                let week_start = NaiveDate::from_ymd_opt(2026, *month, *day).unwrap();
+                (format!("menu-{month}-{day}.pdf"), week_start)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_resolve_duplicate_weeks_tie_break() {
+        let week_start = NaiveDate::from_ymd_opt(2026, 1, 26).unwrap();
+        let other_week = NaiveDate::from_ymd_opt(2026, 2, 2).unwrap();
+        let menus = vec![
+            ("old.pdf".to_string(), week_start),
+            ("other.pdf".to_string(), other_week),
+            ("reupload.pdf".to_string(), week_start),
+        ];
+
+        // Without Last-Modified the later link wins.
+        let senior = |_: &str| DEFAULT_MENU_TYPE.to_string();
+        let (kept, conflicts) = resolve_duplicate_weeks(menus.clone(), senior, |_| None);
+        assert_eq!(
+            kept,
+            vec![
+                ("reupload.pdf".to_string(), week_start),
+                ("other.pdf".to_string(), other_week),
+            ]
+        );
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].menu_type, "senior");
+        assert_eq!(conflicts[0].week_start, "2026-01-26");
+        assert_eq!(conflicts[0].links, vec!["old.pdf", "reupload.pdf"]);
+        assert_eq!(conflicts[0].chosen, "reupload.pdf");
+
+        // A newer Last-Modified on the earlier link takes precedence.
+        let (kept, conflicts) = resolve_duplicate_weeks(menus, senior, |url| {
+            let value = match url {
+                "old.pdf" => "Tue, 27 Jan 2026 09:00:00 GMT",
+                _ => "Mon, 19 Jan 2026 09:00:00 GMT",
+            };
+            chrono::DateTime::parse_from_rfc2822(value).ok()
+        });
+        assert_eq!(kept[0].0, "old.pdf");
+        assert_eq!(conflicts[0].chosen, "old.pdf");
+    }
+
+    #[test]
+    fn test_resolve_duplicate_weeks_per_menu_type() {
+        let week_start = NaiveDate::from_ymd_opt(2026, 1, 26).unwrap();
+        let menus = vec![
+            ("senior.pdf".to_string(), week_start),
+            ("prep.pdf".to_string(), week_start),
+        ];
+        let menu_type = |link: &str| link.trim_end_matches(".pdf").to_string();
+
+        let (kept, conflicts) = resolve_duplicate_weeks(menus.clone(), menu_type, |_| None);
+        assert_eq!(kept, menus);
+        assert!(conflicts.is_empty());
+    }
+
+    #[test]
+    fn test_resolve_target_exact_containing_week() {
+        let menus = published(&[(1, 19), (1, 26)]);
+        let today = NaiveDate::from_ymd_opt(2026, 3, 2).unwrap();
+        let date = NaiveDate::from_ymd_opt(2026, 1, 29).unwrap();
+
+        let (link, resolution) = resolve_target(&menus, date, today).unwrap();
+        assert_eq!(link, "menu-1-26.pdf");
+        assert_eq!(
+            resolution,
+            WeekResolution::Exact(NaiveDate::from_ymd_opt(2026, 1, 26).unwrap())
+        );
+        assert_eq!(resolution.as_str(), "exact");
+    }
+
+    #[test]
+    fn test_resolve_target_week_ahead_of_only_published_week() {
+        let menus = published(&[(1, 26)]);
+        let today = NaiveDate::from_ymd_opt(2026, 1, 28).unwrap();
+        let date = NaiveDate::from_ymd_opt(2026, 2, 4).unwrap();
+
+        let (_, resolution) = resolve_target(&menus, date, today).unwrap();
+        assert_eq!(
+            resolution,
+            WeekResolution::Inferred(NaiveDate::from_ymd_opt(2026, 1, 26).unwrap())
+        );
+        assert_eq!(resolution.as_str(), "inferred");
+    }
+
+    #[test]
+    fn test_inference_respects_max_inference_weeks() {
+        let week_starts = [NaiveDate::from_ymd_opt(2026, 1, 26).unwrap()];
+        let today = NaiveDate::from_ymd_opt(2026, 1, 28).unwrap();
+        // Three weeks ahead of today, so the target is three weeks from the only week.
+        let date = NaiveDate::from_ymd_opt(2026, 2, 18).unwrap();
+
+        assert_eq!(
+            choose_inferred_week_start(&week_starts, date, today, 3),
+            Some(WeekResolution::Inferred(week_starts[0]))
+        );
+        assert_eq!(
+            choose_inferred_week_start(&week_starts, date, today, 2),
+            None
+        );
+        // An exact containing week is never subject to the limit.
+        assert_eq!(
+            choose_inferred_week_start(&week_starts, week_starts[0], today, 0),
+            Some(WeekResolution::Exact(week_starts[0]))
+        );
+    }
+
+    #[test]
+    fn test_previous_published_week() {
+        let menus = published(&[(1, 19), (2, 2), (1, 26)]);
+        let week_start = NaiveDate::from_ymd_opt(2026, 2, 2).unwrap();
+
+        let (_, previous) = previous_published_week(&menus, week_start).unwrap();
+        assert_eq!(*previous, NaiveDate::from_ymd_opt(2026, 1, 26).unwrap());
+        assert!(
+            previous_published_week(&menus, NaiveDate::from_ymd_opt(2026, 1, 19).unwrap())
+                .is_none()
+        );
+        assert_eq!(
+            WeekResolution::PreviousWeekFallback(*previous).as_str(),
+            "previous_week_fallback"
+        );
+    }
+
+    #[test]
+    fn test_meal_query_rejects_unknown_fallback() {
+        let rejection = MealQuery::try_from(QueryParams {
+            date: "2026-01-26".to_string(),
+            period: "lunch".to_string(),
+            explain: false,
+            fallback: Some("next".to_string()),
+        })
+        .unwrap_err();
+
+        assert_eq!(
+            rejection,
+            MealQueryRejection::single("fallback", "Unrecognized fallback. Use fallback=prev.")
+        );
+    }
+
+    #[test]
+    fn test_resolve_target_none_without_published_weeks() {
+        let today = NaiveDate::from_ymd_opt(2026, 1, 28).unwrap();
+        assert!(resolve_target(&[], today, today).is_none());
+    }
+
+    #[test]
+    fn test_week_grid_keys_by_weekday_in_order() {
+        let week_start = NaiveDate::from_ymd_opt(2026, 1, 26).unwrap();
+        let mut menus = HashMap::new();
+        menus.insert(
+            "2026-01-26-lunch".to_string(),
+            "Pasta\nGarlic bread".to_string(),
+        );
+        menus.insert("2026-01-27-dinner".to_string(), "Curry".to_string());
+
+        let grid = week_grid(&structure_week(&menus, week_start));
+        let json = serde_json::to_string(&grid).unwrap();
+
+        assert!(
+            json.starts_with(r#"{"Monday":{"date":"2026-01-26","lunch":["Pasta","Garlic bread"]}"#)
+        );
+        assert!(json.find("Tuesday").unwrap() < json.find("Sunday").unwrap());
+        let value: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["Tuesday"]["dinner"][0], "Curry");
+        assert_eq!(value["Sunday"]["date"], "2026-02-01");
+    }
+
+    #[test]
+    fn test_structure_week_detects_theme() {
+        let week_start = NaiveDate::from_ymd_opt(2026, 1, 19).unwrap();
+        let mut menus = HashMap::new();
+        menus.insert(
+            "2026-01-23-dinner".to_string(),
+            "Burns Night Supper\nHaggis, neeps and tatties".to_string(),
+        );
+        menus.insert("2026-01-22-dinner".to_string(), "Fish pie".to_string());
+
+        let week = structure_week(&menus, week_start);
+
+        assert_eq!(week.days[4].theme.as_deref(), Some("Burns Night"));
+        assert_eq!(week.days[4].meals[0].items.len(), 2);
+        assert_eq!(week.days[3].theme, None);
+    }
+
+    #[test]
+    fn test_parse_dish_tags_and_allergens() {
+        assert_eq!(
+            parse_dish("Nut roast (V) (contains nuts, celery)"),
+            Dish {
+                name: "Nut roast".to_string(),
+                dietary: vec!["vegetarian".to_string()],
+                allergens: vec!["nuts".to_string(), "celery".to_string()],
+            }
+        );
+
+        let dish = parse_dish("Lentil dahl (VE/GF, DF)");
+        assert_eq!(dish.name, "Lentil dahl");
+        assert_eq!(dish.dietary, ["vegan", "gluten_free", "dairy_free"]);
+        assert!(dish.allergens.is_empty());
+
+        // Brackets that aren't markers stay in the name.
+        let dish = parse_dish("Fish (of the day) and chips");
+        assert_eq!(dish.name, "Fish (of the day) and chips");
+        assert!(dish.dietary.is_empty());
+    }
+
+    #[test]
+    fn test_prioritize_weeks_nearest_first() {
+        let mut menus = published(&[(1, 5), (1, 12), (1, 19), (1, 26), (2, 2)]);
+        let today = NaiveDate::from_ymd_opt(2026, 1, 21).unwrap();
+
+        prioritize_weeks(&mut menus, today, Some(3));
+
+        let week_starts: Vec<String> = menus.iter().map(|(_, ws)| format_date(*ws)).collect();
+        assert_eq!(week_starts, vec!["2026-01-19", "2026-01-26", "2026-01-12"]);
+
+        let sunday = NaiveDate::from_ymd_opt(2026, 1, 25).unwrap();
+        let mut menus = published(&[(1, 19), (1, 26)]);
+        prioritize_weeks(&mut menus, sunday, Some(1));
+        assert_eq!(menus[0].1, NaiveDate::from_ymd_opt(2026, 1, 19).unwrap());
+
+        let mut menus = published(&[(1, 5), (1, 26)]);
+        prioritize_weeks(&mut menus, today, None);
+        assert_eq!(menus.len(), 2);
+        assert_eq!(menus[0].1, NaiveDate::from_ymd_opt(2026, 1, 26).unwrap());
+    }
+
+    #[test]
+    fn test_day_response_slices_inferred_week() {
+        let week_start = NaiveDate::from_ymd_opt(2026, 1, 19).unwrap();
+        let mut menus = HashMap::new();
+        menus.insert(
+            "2026-01-21-lunch".to_string(),
+            "Pasta\nGarlic bread".to_string(),
+        );
+        menus.insert("2026-01-21-dinner".to_string(), "Curry".to_string());
+        menus.insert("2026-01-22-lunch".to_string(), "Fish pie".to_string());
+        let week = ResolvedWeek {
+            week_start,
+            link: "menu.pdf".to_string(),
+            menus,
+            notes: WeekNotes {
+                notes: vec!["All dishes subject to availability".to_string()],
+                last_updated: None,
+            },
+            published: vec![week_start],
+            stale: false,
+            resolution: WeekResolution::Inferred(week_start),
+        };
+
+        // A Wednesday one week on maps onto the inferred week's Wednesday.
+        let day = day_response(&week, NaiveDate::from_ymd_opt(2026, 1, 28).unwrap());
+
+        assert_eq!(day.date, "2026-01-28");
+        assert_eq!(day.weekday, "Wednesday");
+        assert_eq!(day.week_start, "2026-01-19");
+        assert_eq!(day.meals.len(), 2);
+        assert_eq!(day.meals[0].period, "lunch");
+        assert_eq!(day.meals[0].items, vec!["Pasta", "Garlic bread"]);
+        assert_eq!(day.meals[0].dishes[1].name, "Garlic bread");
+        assert_eq!(day.notes.notes.len(), 1);
+    }
+
+    #[test]
+    fn test_week_neighbors() {
+        let week_start = NaiveDate::from_ymd_opt(2026, 1, 26).unwrap();
+        let published = vec![NaiveDate::from_ymd_opt(2026, 1, 19).unwrap(), week_start];
+
+        let (prev_week, next_week) = week_neighbors(week_start, &published);
+
+        assert_eq!(prev_week.week_start, "2026-01-19");
+        assert!(prev_week.published);
+        assert_eq!(next_week.week_start, "2026-02-02");
+        assert!(!next_week.published);
+    }
+
+    #[test]
+    fn test_render_week_markdown() {
+        let week_start = NaiveDate::from_ymd_opt(2026, 1, 26).unwrap();
+        let mut menus = HashMap::new();
+        menus.insert(
+            "2026-01-26-lunch".to_string(),
+            "Chicken pie\nVeg | gravy".to_string(),
+        );
+        menus.insert("2026-01-27-lunch".to_string(), "Curry".to_string());
+
+        let markdown = render_week_markdown(&structure_week(&menus, week_start));
+
+        assert!(markdown.starts_with("# Menu for week commencing 2026-01-26\n"));
+        assert!(markdown.contains("## Lunch\n"));
+        assert!(!markdown.contains("## Dinner"));
+        assert!(markdown.contains("| Monday 2026-01-26 | • Chicken pie<br>• Veg \\| gravy |\n"));
+        assert!(markdown.contains("| Tuesday 2026-01-27 | Curry |\n"));
+    }
+
+    #[tokio::test]
+    async fn test_get_day_reports_bad_month() {
+        use tower::ServiceExt;
+
+        let request = axum::http::Request::builder()
+            .uri("/day?date=2026-13-01")
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let response = build_router(config::config(), CorsLayer::new())
+            .oneshot(request)
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["error"], "Invalid date: month must be 1–12 (got 13).");
+    }
+
+    #[tokio::test]
+    async fn test_request_id_is_echoed_and_added_to_errors() {
+        use tower::ServiceExt;
+
+        let request = axum::http::Request::builder()
+            .uri("/meal?date=2026-01-31&period=lunch")
+            .header("x-request-id", "abc-123")
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let response = build_router(config::config(), CorsLayer::new())
+            .oneshot(request)
+            .await
+            .unwrap();
+
+        assert_eq!(response.headers()["x-request-id"], "abc-123");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["request_id"], "abc-123");
+
+        // Plain-text handler errors are wrapped as JSON too.
+        let request = axum::http::Request::builder()
+            .uri("/week?date=not-a-date")
+            .header("x-request-id", "abc-456")
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let response = build_router(config::config(), CorsLayer::new())
+            .oneshot(request)
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/json");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["request_id"], "abc-456");
+        assert!(json["error"].as_str().unwrap().starts_with("Invalid date"));
+    }
+
+    #[tokio::test]
+    async fn test_post_meals_rejects_oversized_batch() {
+        use tower::ServiceExt;
+
+        let items: Vec<Value> = (0..=DEFAULT_MAX_BATCH_SIZE)
+            .map(|_| serde_json::json!({"date": "2026-01-26", "period": "lunch"}))
+            .collect();
+        let request = axum::http::Request::builder()
+            .method(Method::POST)
+            .uri("/meals")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(axum::body::Body::from(serde_json::to_vec(&items).unwrap()))
+            .unwrap();
+        let response = build_router(config::config(), CorsLayer::new())
+            .oneshot(request)
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_request_body_limit() {
+        use tower::ServiceExt;
+
+        let body = vec![b' '; config::config().max_body_bytes + 1];
+        let request = axum::http::Request::builder()
+            .method(Method::POST)
+            .uri("/meals")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(axum::body::Body::from(body))
+            .unwrap();
+        let response = build_router(config::config(), CorsLayer::new())
+            .oneshot(request)
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[test]
+    fn test_sanitize_param() {
+        assert_eq!(
+            sanitize_param("period", "lun\u{0}ch\r\n", MAX_PERIOD_PARAM_LEN),
+            Ok("lunch".to_string())
+        );
+        assert_eq!(
+            sanitize_param(
+                "period",
+                &"a".repeat(MAX_PERIOD_PARAM_LEN),
+                MAX_PERIOD_PARAM_LEN
+            )
+            .map(|value| value.len()),
+            Ok(MAX_PERIOD_PARAM_LEN)
+        );
+        assert_eq!(
+            sanitize_param("period", &"a".repeat(500), MAX_PERIOD_PARAM_LEN),
+            Err("The 'period' parameter is too long (max 64 characters).".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_sanitized_query() {
+        let uri: axum::http::Uri = "/search?q=fish%00%0Apie&fuzzy=true".parse().unwrap();
+        let params = parse_sanitized_query::<SearchParams>(&uri).unwrap();
+        assert_eq!(params.q, "fishpie");
+        assert!(params.fuzzy);
+
+        let uri: axum::http::Uri = format!("/term?name={}", "a".repeat(MAX_PARAM_LEN + 1))
+            .parse()
+            .unwrap();
+        assert_eq!(
+            parse_sanitized_query::<TermParams>(&uri).err(),
+            Some("The 'name' parameter is too long (max 64 characters).".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_every_query_endpoint_limits_parameter_length() {
+        use tower::ServiceExt;
+
+        let long_date = "1".repeat(MAX_DATE_PARAM_LEN + 1);
+        let uris = [
+            format!("/day?date={long_date}"),
+            format!("/week?date={long_date}"),
+            format!("/week.md?date={long_date}"),
+            format!("/week/grid?date={long_date}"),
+            format!("/term?name={}", "a".repeat(MAX_PARAM_LEN + 1)),
+            format!("/search?q={}", "a".repeat(MAX_SEARCH_PARAM_LEN + 1)),
+        ];
+        for uri in uris {
+            let request = axum::http::Request::builder()
+                .uri(&uri)
+                .body(axum::body::Body::empty())
+                .unwrap();
+            let response = build_router(config::config(), CorsLayer::new())
+                .oneshot(request)
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{uri}");
+        }
+    }
+
+    #[test]
+    fn test_meal_query_rejects_long_period() {
+        let rejection = MealQuery::try_from(QueryParams {
+            date: "2026-01-26".to_string(),
+            period: "lunch".repeat(100),
+            explain: false,
+            fallback: None,
         })
-        .and_then(|v| {
-            // If it's a string, try to deserialize it directly to the target type
-            if let Some(s) = v.as_str() {
-                // First try to deserialize the string directly to T
-                if let Ok(result) = serde_json::from_str::<T>(s) {
-                    return Some(result);
-                }
-                // If that fails, try wrapping it in quotes and deserializing
-                let quoted = format!("\"{}\"", s);
-                if let Ok(result) = serde_json::from_str::<T>(&quoted) {
-                    return Some(result);
-                }
+        .unwrap_err();
+
+        assert_eq!(rejection.errors[0].field, "period");
+        assert_eq!(rejection.into_response().status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn test_parse_catering_pages() {
+        let page = |menu_type: &str, url: &str| CateringPage {
+            url: url.to_string(),
+            menu_type: menu_type.to_string(),
+        };
+        assert_eq!(
+            parse_catering_pages("", CATERING_PAGE_URL),
+            vec![page("senior", CATERING_PAGE_URL)]
+        );
+        assert_eq!(
+            parse_catering_pages(
+                " https://a.example/menus/ , https://b.example/prep/,https://a.example/menus/",
+                CATERING_PAGE_URL
+            ),
+            vec![
+                page("senior", "https://a.example/menus/"),
+                page("senior", "https://b.example/prep/"),
+            ]
+        );
+        assert_eq!(
+            parse_catering_pages(
+                "https://a.example/menus/?site=1, Prep = https://b.example/prep/",
+                CATERING_PAGE_URL
+            ),
+            vec![
+                page("senior", "https://a.example/menus/?site=1"),
+                page("prep", "https://b.example/prep/"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_allowed_hosts_follow_catering_pages() {
+        let pages = [
+            CATERING_PAGE_URL.to_string(),
+            "https://prep.example/catering/".to_string(),
+        ];
+        assert_eq!(
+            parse_allowed_hosts(&pages, " cdn.example , www.cranbrookschool.co.uk"),
+            ["www.cranbrookschool.co.uk", "prep.example", "cdn.example"]
+        );
+    }
+
+    #[test]
+    fn test_merge_page_links_dedupes_across_pages() {
+        let week = NaiveDate::from_ymd_opt(2026, 1, 26);
+        let senior = CateringPage {
+            url: "https://www.cranbrookschool.co.uk/catering/".to_string(),
+            menu_type: "senior".to_string(),
+        };
+        let prep = CateringPage {
+            url: "https://www.cranbrookschool.co.uk/prep/catering/".to_string(),
+            menu_type: "prep".to_string(),
+        };
+        let (links, sources) = merge_page_links(vec![
+            (
+                senior.clone(),
+                vec![("https://x/senior-menu.pdf".to_string(), week)],
+            ),
+            (
+                prep.clone(),
+                vec![
+                    ("https://x/senior-menu.pdf".to_string(), None),
+                    ("https://x/prep-menu.pdf".to_string(), week),
+                ],
+            ),
+        ]);
+
+        assert_eq!(
+            links,
+            vec![
+                ("https://x/senior-menu.pdf".to_string(), week),
+                ("https://x/prep-menu.pdf".to_string(), week),
+            ]
+        );
+        assert_eq!(sources["https://x/senior-menu.pdf"].page, senior.url);
+        assert_eq!(sources["https://x/prep-menu.pdf"].page, prep.url);
+        assert_eq!(sources["https://x/prep-menu.pdf"].menu_type, "prep");
+    }
+
+    #[test]
+    fn test_menu_table_page() {
+        let link = menu_table_link(CATERING_PAGE_URL, 2);
+        assert_eq!(menu_table_page(&link), CATERING_PAGE_URL);
+        assert_eq!(menu_table_index(&link), Some(2));
+    }
+
+    #[tokio::test]
+    async fn test_page_without_menu_anchors_yields_no_links() {
+        let page = r#"<html><body>
+            <h1>Cranbrook Catering</h1>
+            <p>Our menus are changing - see the new app.</p>
+            <a href="/contact/">Contact us</a>
+            <a href="/documents/catering-policy.pdf">Catering policy</a>
+        </body></html>"#;
+        let base = Url::parse(BASE_URL).unwrap();
+
+        let links = page_menu_links(page, CATERING_PAGE_URL, &base, &allowed_hosts());
+        assert!(links.is_empty());
+        let (links, _) = merge_page_links(vec![(
+            CateringPage {
+                url: CATERING_PAGE_URL.to_string(),
+                menu_type: DEFAULT_MENU_TYPE.to_string(),
+            },
+            links,
+        )]);
+        assert!(links.is_empty());
+
+        let response = no_menus_response();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["status"], "no_menus_found");
+    }
+
+    #[test]
+    fn test_stored_meal_lookup() {
+        let index = store::Index::from([
+            ("2026-01-19-dinner".to_string(), "Roast".to_string()),
+            ("2026-01-26-lunch".to_string(), "Curry".to_string()),
+            (
+                "prep:2026-02-02-lunch".to_string(),
+                "Fish fingers".to_string(),
+            ),
+        ]);
+        let today = NaiveDate::from_ymd_opt(2026, 1, 28).unwrap();
+        let monday = NaiveDate::from_ymd_opt(2026, 1, 26).unwrap();
+        let lookup = |date, period, fallback_prev| {
+            stored_meal_lookup(&index, date, period, fallback_prev, today)
+        };
+
+        assert_eq!(
+            stored_index_weeks(&index)
+                .into_iter()
+                .map(|(_, week_start)| week_start)
+                .collect::<Vec<_>>(),
+            [NaiveDate::from_ymd_opt(2026, 1, 19).unwrap(), monday]
+        );
+        assert_eq!(
+            lookup(monday, "lunch", false).unwrap(),
+            Some("Curry".to_string())
+        );
+        assert_eq!(lookup(monday, "dinner", false).unwrap(), None);
+        assert_eq!(
+            lookup(monday, "dinner", true).unwrap(),
+            Some("Roast".to_string())
+        );
+        // The prep week doesn't count as published, so the next week is pending.
+        let next_tuesday = NaiveDate::from_ymd_opt(2026, 2, 3).unwrap();
+        assert!(matches!(
+            lookup(next_tuesday, "lunch", false),
+            Err(CateringError::NotYetPublished { .. })
+        ));
+    }
+
+    #[test]
+    fn test_is_pending_publication_window() {
+        let published = [
+            NaiveDate::from_ymd_opt(2026, 1, 19).unwrap(),
+            NaiveDate::from_ymd_opt(2026, 1, 26).unwrap(),
+        ];
+        let date = |month, day| NaiveDate::from_ymd_opt(2026, month, day).unwrap();
+
+        // Inside the latest published week.
+        assert!(!is_pending_publication(&published, date(1, 30), 14));
+        // Next week and the one after are pending.
+        assert!(is_pending_publication(&published, date(2, 2), 14));
+        assert!(is_pending_publication(&published, date(2, 15), 14));
+        // Further out falls back to the normal lookup.
+        assert!(!is_pending_publication(&published, date(2, 16), 14));
+        assert!(!is_pending_publication(&[], date(2, 2), 14));
+    }
+
+    #[test]
+    fn test_ensure_published_rejects_pending_dates() {
+        let menus = published(&[(1, 19), (1, 26)]);
+        let date = |month, day| NaiveDate::from_ymd_opt(2026, month, day).unwrap();
+
+        assert!(ensure_published(&menus, date(1, 30)).is_ok());
+        let err = anyhow::Error::from(ensure_published(&menus, date(2, 3)).unwrap_err());
+        assert!(is_not_yet_published(&err));
+        assert!(!is_not_yet_published(&anyhow::anyhow!("timed out")));
+    }
+
+    #[test]
+    fn test_disk_cache_path_is_sanitized_and_unique() {
+        let dir = std::path::Path::new("/cache");
+        let a = disk_cache_path(
+            dir,
+            "https://www.cranbrookschool.co.uk/menus/Week 1 Menu.pdf",
+        );
+        let b = disk_cache_path(
+            dir,
+            "https://www.cranbrookschool.co.uk/menus/Week_1_Menu.pdf",
+        );
+
+        let name = a.file_name().unwrap().to_str().unwrap();
+        assert!(name.ends_with("menus_Week_1_Menu.pdf"));
+        assert!(!name.contains('/') && !name.contains(' '));
+        assert_eq!(a.parent(), Some(dir));
+        assert_ne!(a, b);
+    }
+
+    #[tokio::test]
+    async fn test_write_atomically_replaces_file() {
+        let dir = std::env::temp_dir().join(format!("catering-test-{}", std::process::id()));
+        let path = dir.join("menu.pdf");
+
+        write_atomically(&path, b"first").await.unwrap();
+        write_atomically(&path, b"second").await.unwrap();
+
+        assert_eq!(std::fs::read(&path).unwrap(), b"second");
+        let leftovers = std::fs::read_dir(&dir).unwrap().count();
+        assert_eq!(leftovers, 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_parse_term_dates_and_find_term() {
+        let terms = parse_term_dates(DEFAULT_TERM_DATES).unwrap();
+        assert_eq!(terms.len(), 6);
+
+        let lent = find_term(&terms, "Lent", 2026).unwrap();
+        assert_eq!(lent.start, NaiveDate::from_ymd_opt(2026, 1, 6).unwrap());
+        assert_eq!(lent.end, NaiveDate::from_ymd_opt(2026, 3, 27).unwrap());
+        assert!(find_term(&terms, "michaelmas", 2026).is_some());
+        assert!(find_term(&terms, "hilary", 2026).is_none());
+
+        // Without a year, Lent in October is the coming January's.
+        let october = NaiveDate::from_ymd_opt(2026, 10, 14).unwrap();
+        let lent = find_academic_term(&terms, "lent", october).unwrap();
+        assert_eq!(lent.start, NaiveDate::from_ymd_opt(2027, 1, 5).unwrap());
+        let march = NaiveDate::from_ymd_opt(2026, 3, 1).unwrap();
+        let michaelmas = find_academic_term(&terms, "Michaelmas", march).unwrap();
+        assert_eq!(
+            michaelmas.start,
+            NaiveDate::from_ymd_opt(2025, 9, 3).unwrap()
+        );
+
+        assert!(parse_term_dates("lent:2026-01-06").is_err());
+        assert!(parse_term_dates("lent:2026-03-27:2026-01-06").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_get_term_rejects_unknown_term() {
+        let response = get_term(
+            State(config::config()),
+            SanitizedQuery(TermParams {
+                name: "hilary".to_string(),
+                year: Some(2026),
+            }),
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_stream_index_json_large_index() {
+        let weeks = async_stream::stream! {
+            for week in 0..300 {
+                let menus: HashMap<String, String> = (0..12)
+                    .map(|slot| (format!("w{week:03}-{slot:02}"), format!("Dish \"{week}\"\n{slot}")))
+                    .collect();
+                yield Ok(menus);
             }
-            serde_json::from_value(v.clone()).ok()
+            // Weeks with no parsed meals must not leave a dangling comma.
+            yield Ok(HashMap::new());
+            // Nor may a week that failed to load.
+            yield Err(anyhow::anyhow!("PDF unreachable"));
+            yield Ok(HashMap::from([("last".to_string(), "Curry".to_string())]));
+        };
+
+        let body = axum::body::to_bytes(
+            axum::body::Body::from_stream(stream_index_json(weeks)),
+            usize::MAX,
+        )
+        .await
+        .unwrap();
+        let json: serde_json::Map<String, Value> = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(json.len(), 300 * 12 + 1);
+        assert_eq!(json["w042-07"], "Dish \"42\"\n7");
+        assert_eq!(json["last"], "Curry");
+    }
+
+    #[tokio::test]
+    async fn test_stream_index_json_empty() {
+        let empty: Vec<anyhow::Result<HashMap<String, String>>> = Vec::new();
+        let weeks = async_stream::stream! {
+            for week in empty {
+                yield week;
+            }
+        };
+        let body = axum::body::to_bytes(
+            axum::body::Body::from_stream(stream_index_json(weeks)),
+            usize::MAX,
+        )
+        .await
+        .unwrap();
+        assert_eq!(&body[..], b"{}");
+    }
+
+    #[test]
+    fn test_content_hash_is_hex_sha256() {
+        assert_eq!(
+            content_hash(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn test_mark_stale_sets_header_only_when_stale() {
+        let fresh = mark_stale(StatusCode::OK.into_response(), false);
+        assert!(fresh.headers().get("x-data-stale").is_none());
+
+        let stale = mark_stale(StatusCode::OK.into_response(), true);
+        assert_eq!(stale.headers()["x-data-stale"], "true");
+    }
+
+    #[test]
+    fn test_request_id_generated_when_missing_or_invalid() {
+        let generated = request_id::request_id_from(None);
+        assert_eq!(generated.len(), 36);
+
+        let invalid = HeaderValue::from_static("has spaces");
+        assert_ne!(request_id::request_id_from(Some(&invalid)), "has spaces");
+
+        let valid = HeaderValue::from_static("req-42");
+        assert_eq!(request_id::request_id_from(Some(&valid)), "req-42");
+    }
+
+    /// Serialises tests that seed the global cache's catering pages.
+    static SEEDED_CACHE: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+    const SEEDED_LINK: &str =
+        "https://www.cranbrookschool.co.uk/wp-content/uploads/Menu-w-c-26-01.pdf";
+
+    /// Serves `html` as every configured catering page, and `menus` as the
+    /// cached week of 26 January 2026 linked from it.
+    fn seed_catering_pages(html: &str, menus: &[(&str, &str)]) {
+        let mut cache = lock_cache();
+        for page_url in catering_page_urls() {
+            cache.set_landing_page(&page_url, html.to_string());
+        }
+        cache.insert_week(
+            NaiveDate::from_ymd_opt(2026, 1, 26).unwrap(),
+            CachedWeek {
+                link: SEEDED_LINK.to_string(),
+                menus: menus
+                    .iter()
+                    .map(|(key, meal)| (key.to_string(), meal.to_string()))
+                    .collect(),
+                notes: WeekNotes::default(),
+                fetched_at: std::time::Instant::now(),
+            },
+        );
+    }
+
+    #[tokio::test]
+    async fn test_head_meal_matches_get_without_body() {
+        use tower::ServiceExt;
+
+        let _seeded = SEEDED_CACHE.lock().await;
+        seed_catering_pages(
+            r#"<a href="/wp-content/uploads/Menu-w-c-26-01.pdf">Menu for w/c Monday 26th January 2026</a>"#,
+            &[("2026-01-27-lunch", "Pasta")],
+        );
+
+        let request = |method: Method| {
+            axum::http::Request::builder()
+                .method(method)
+                .uri("/meal?date=2026-01-27&period=lunch")
+                .body(axum::body::Body::empty())
+                .unwrap()
+        };
+        let router = build_router(config::config(), CorsLayer::new());
+
+        let get = router.clone().oneshot(request(Method::GET)).await.unwrap();
+        let head = router.oneshot(request(Method::HEAD)).await.unwrap();
+
+        assert_eq!(get.status(), StatusCode::OK);
+        assert_eq!(head.status(), get.status());
+        assert!(get.headers().contains_key(header::ETAG));
+        for name in [header::CONTENT_TYPE, header::CONTENT_LENGTH, header::ETAG] {
+            assert_eq!(
+                head.headers().get(&name),
+                get.headers().get(&name),
+                "{name}"
+            );
+        }
+        let body = axum::body::to_bytes(head.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert!(body.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_lookups_answer_503_when_no_menus_are_listed() {
+        use tower::ServiceExt;
+
+        let _seeded = SEEDED_CACHE.lock().await;
+        seed_catering_pages("<p>Menus are coming soon.</p>", &[]);
+
+        let links = fetch_menu_links(&build_client().unwrap()).await.unwrap();
+        assert!(links.is_empty());
+        assert!(no_menus_found());
+
+        let response = get_meal(MealQuery {
+            date: NaiveDate::from_ymd_opt(2026, 1, 27).unwrap(),
+            periods: vec![MealPeriod::Lunch],
+            explain: false,
+            fallback_prev: false,
         })
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        let requests = [
+            ("GET", "/meal?date=2026-01-27&period=lunch,dinner", ""),
+            ("GET", "/day?date=2026-01-27", ""),
+            ("GET", "/week?date=2026-01-27", ""),
+            ("GET", "/term?name=lent&year=2026", ""),
+            ("GET", "/menu.json", ""),
+            ("GET", "/search?q=curry", ""),
+            (
+                "POST",
+                "/meals",
+                r#"[{"date": "2026-01-27", "period": "lunch"}]"#,
+            ),
+        ];
+        for (method, uri, body) in requests {
+            let request = axum::http::Request::builder()
+                .method(method)
+                .uri(uri)
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(axum::body::Body::from(body))
+                .unwrap();
+            let response = build_router(config::config(), CorsLayer::new())
+                .oneshot(request)
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE, "{uri}");
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            let json: Value = serde_json::from_slice(&body).unwrap();
+            assert_eq!(json["status"], "no_menus_found", "{uri}");
+        }
+
+        lock_cache().set_no_menus_found(false);
+    }
+
+    #[tokio::test]
+    async fn test_relative_menu_links_resolve_against_their_page() {
+        let _seeded = SEEDED_CACHE.lock().await;
+        seed_catering_pages(
+            r#"<a href="menus/Menu-w-c-26-01.pdf">Menu for w/c Monday 26th January 2026</a>"#,
+            &[],
+        );
+
+        let links = fetch_menu_links(&build_client().unwrap()).await.unwrap();
+        let page = Url::parse(&catering_page_urls()[0]).unwrap();
+        assert_eq!(
+            links[0].0,
+            page.join("menus/Menu-w-c-26-01.pdf").unwrap().as_str()
+        );
+        assert_eq!(lock_cache().menu_type(&links[0].0), DEFAULT_MENU_TYPE);
+    }
+
+    #[test]
+    fn test_json_with_etag_is_stable() {
+        let first = json_with_etag(&serde_json::json!({ "meal": "Pasta" }));
+        let second = json_with_etag(&serde_json::json!({ "meal": "Pasta" }));
+        let other = json_with_etag(&serde_json::json!({ "meal": "Curry" }));
+
+        let etag = first.headers().get(header::ETAG).unwrap();
+        assert_eq!(Some(etag), second.headers().get(header::ETAG));
+        assert_ne!(Some(etag), other.headers().get(header::ETAG));
+    }
+
+    #[test]
+    fn test_parse_weekly_menu_fortnightly_pdf() {
+        let text = "Cranbrook School Catering\n\
+            Week Commencing Monday 26th January 2026\n\
+            Dinner Dinner Dinner Dinner Dinner Dinner Dinner\n\
+            Roast chicken\n \
+            Beef stew\n \
+            Fish pie\n \
+            Lasagne\n \
+            Curry\n \
+            Pizza\n \
+            Roast beef\n\
+            Week Commencing Monday 2nd February 2026\n\
+            Dinner Dinner Dinner Dinner Dinner Dinner Dinner\n\
+            Chilli\n \
+            Risotto\n \
+            Sausages\n \
+            Pie\n \
+            Fajitas\n \
+            Burgers\n \
+            Roast pork\n";
+        let week_start = NaiveDate::from_ymd_opt(2026, 1, 26).unwrap();
+
+        let sections = split_week_sections(text);
+        assert_eq!(sections.len(), 2);
+        assert_eq!(sections[1].0, NaiveDate::from_ymd_opt(2026, 2, 2).unwrap());
+
+        let menus = parse_weekly_menu(text, week_start);
+        assert_eq!(menus["2026-01-26-dinner"], "Roast chicken");
+        assert_eq!(menus["2026-02-01-dinner"], "Roast beef");
+        assert_eq!(menus["2026-02-02-dinner"], "Chilli");
+        assert_eq!(menus["2026-02-08-dinner"], "Roast pork");
+        let link = "https://example.com/fortnight.pdf";
+        assert_eq!(
+            pdf_week_starts(link, text, Some(week_start)),
+            [week_start, NaiveDate::from_ymd_opt(2026, 2, 2).unwrap()]
+        );
+        // Each week is cached under its own start, holding only its own days.
+        let weeks = parse_pdf_weeks(link, text, &[week_start]);
+        assert_eq!(weeks.len(), 2);
+        assert_eq!(weeks[1].0, NaiveDate::from_ymd_opt(2026, 2, 2).unwrap());
+        assert_eq!(weeks[1].1.menus["2026-02-02-dinner"], "Chilli");
+        assert!(!weeks[1].1.menus.contains_key("2026-01-26-dinner"));
+        assert!(!weeks[0].1.menus.contains_key("2026-02-02-dinner"));
+    }
+
+    #[test]
+    fn test_merge_continuations() {
+        let lines = |items: &[&str]| items.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(
+            merge_continuations(&lines(&[
+                "Chicken tikka masala with",
+                "basmati rice and naan",
+                "Sticky toffee pudding",
+                "& custard",
+            ])),
+            vec![
+                "Chicken tikka masala with basmati rice and naan",
+                "Sticky toffee pudding & custard",
+            ]
+        );
+        assert_eq!(
+            merge_continuations(&lines(&["Roast chicken", "Yorkshire pudding"])),
+            vec!["Roast chicken", "Yorkshire pudding"]
+        );
+    }
+
+    #[test]
+    fn test_parse_weekly_menu_merges_wrapped_dishes() {
+        let week_start = NaiveDate::from_ymd_opt(2026, 1, 26).unwrap();
+        let text = "Lunch Lunch Lunch Lunch Lunch\n\
+            Chicken tikka masala with\n\
+            basmati rice and naan\n \
+            Pasta bake\n \
+            Fish and chips\n \
+            Chilli con carne\n\
+            (served with rice)\n\
+            Jacket potatoes\n \
+            Quiche\n";
+
+        let menus =
+            parse_weekly_menu_with(text, week_start, &SectionKeywords::default(), Weekday::Mon);
+
+        assert_eq!(
+            menus["2026-01-26-lunch"],
+            "Chicken tikka masala with basmati rice and naan"
+        );
+        assert_eq!(
+            menus["2026-01-29-lunch"],
+            "Chilli con carne (served with rice)\nJacket potatoes"
+        );
+        assert_eq!(menus["2026-01-30-lunch"], "Quiche");
+    }
+
+    #[test]
+    fn test_parse_weekly_menu_supper_synonym() {
+        let week_start = NaiveDate::from_ymd_opt(2026, 1, 26).unwrap();
+        let text = "Supper Supper Supper Supper Supper Supper Supper\n\
+            Roast chicken\n \
+            Beef stew\n \
+            Fish pie\n \
+            Lasagne\n \
+            Curry\n \
+            Pizza\n \
+            Roast beef\n";
+
+        assert!(
+            parse_weekly_menu_with(text, week_start, &SectionKeywords::default(), Weekday::Mon)
+                .is_empty()
+        );
+
+        let mut keywords = SectionKeywords::default();
+        keywords.dinner.push("supper".to_string());
+        let menus = parse_weekly_menu_with(text, week_start, &keywords, Weekday::Mon);
+
+        assert_eq!(menus["2026-01-26-dinner"], "Roast chicken");
+        assert_eq!(menus["2026-02-01-dinner"], "Roast beef");
+    }
+
+    const CHRISTMAS_DINNER_MENU: &str = "Cranbrook School\n\
+        Christmas Dinner\n\
+        Thursday 17th December 2026\n\
+        \n\
+        Roast turkey with pigs in blankets\n\
+        and cranberry sauce\n\
+        Nut roast (V)\n\
+        Christmas pudding & brandy sauce\n";
+
+    #[test]
+    fn test_parse_single_day_event_menu() {
+        let date = find_menu_date(CHRISTMAS_DINNER_MENU).unwrap();
+        assert_eq!(date, NaiveDate::from_ymd_opt(2026, 12, 17).unwrap());
+
+        let week_start = NaiveDate::from_ymd_opt(2026, 12, 14).unwrap();
+        assert!(parse_weekly_menu(CHRISTMAS_DINNER_MENU, week_start).is_empty());
+
+        let week = parse_cached_week(
+            "https://example.com/xmas.pdf",
+            CHRISTMAS_DINNER_MENU,
+            week_start,
+        );
+        assert_eq!(week.menus.len(), 1);
+        assert_eq!(
+            week.menus["2026-12-17-dinner"],
+            "Christmas Dinner\n\
+             Roast turkey with pigs in blankets and cranberry sauce\n\
+             Nut roast (V)\n\
+             Christmas pudding & brandy sauce"
+        );
+    }
+
+    #[test]
+    fn test_parse_single_day_menu_with_period_headers() {
+        let date = NaiveDate::from_ymd_opt(2026, 6, 20).unwrap();
+        let text = "Founders' Day\nLunch\nBBQ burgers\nDinner\nHog roast\n";
+        let menus = parse_single_day_menu(text, date);
+
+        assert_eq!(menus.len(), 2);
+        assert_eq!(menus["2026-06-20-lunch"], "BBQ burgers");
+        assert_eq!(menus["2026-06-20-dinner"], "Hog roast");
+    }
+
+    #[test]
+    fn test_failed_weekly_parse_is_not_a_single_day_menu() {
+        let week_start = NaiveDate::from_ymd_opt(2026, 1, 26).unwrap();
+        let text = "Week commencing 26th January 2026
+            Monday Tuesday Wednesday Thursday Friday
+            Soup Pasta bake Fish and chips Chilli Quiche
+";
+        assert!(parse_weekly_menu(text, week_start).is_empty());
+        assert!(!looks_like_single_day_menu(text));
+
+        let week = parse_cached_week("https://example.com/menu.pdf", text, week_start);
+        assert!(week.menus.is_empty());
+        assert!(looks_like_single_day_menu(CHRISTMAS_DINNER_MENU));
+    }
+
+    #[test]
+    fn test_parse_weekly_menu_sunday_start() {
+        let week_start = NaiveDate::from_ymd_opt(2026, 1, 25).unwrap();
+        let text = "Lunch Lunch Lunch Lunch Lunch\n\
+            Soup\n \
+            Pasta bake\n \
+            Fish and chips\n \
+            Chilli\n \
+            Quiche\n\
+            Dinner Dinner Dinner Dinner Dinner Dinner Dinner\n\
+            Roast beef\n \
+            Roast chicken\n \
+            Beef stew\n \
+            Fish pie\n \
+            Lasagne\n \
+            Curry\n \
+            Pizza\n";
+
+        let menus =
+            parse_weekly_menu_with(text, week_start, &SectionKeywords::default(), Weekday::Sun);
+
+        assert_eq!(menus["2026-01-26-lunch"], "Soup");
+        assert_eq!(menus["2026-01-30-lunch"], "Quiche");
+        assert!(!menus.contains_key("2026-01-25-lunch"));
+        assert_eq!(menus["2026-01-25-dinner"], "Roast beef");
+        assert_eq!(menus["2026-01-31-dinner"], "Pizza");
+    }
+
+    #[test]
+    fn test_weekday_offset_sunday_start() {
+        let week_start = NaiveDate::from_ymd_opt(2026, 1, 25).unwrap();
+        assert_eq!(weekday_offset(Weekday::Sun, Weekday::Sun), 0);
+        assert_eq!(weekday_offset(Weekday::Mon, Weekday::Sun), 1);
+        assert_eq!(weekday_offset(Weekday::Sat, Weekday::Sun), 6);
+        assert_eq!(
+            weekday_date(week_start, Weekday::Fri, Weekday::Sun),
+            NaiveDate::from_ymd_opt(2026, 1, 30).unwrap()
+        );
+        assert_eq!(weekday_offset(Weekday::Sun, Weekday::Mon), 6);
+    }
+
+    #[test]
+    fn test_parse_weekly_menu_merged_brunch_header() {
+        let week_start = NaiveDate::from_ymd_opt(2026, 1, 26).unwrap();
+        let text = "Brunch\n\
+            Full English breakfast\n \
+            Pancakes and berries\n\
+            Lunch Lunch Lunch Lunch Lunch\n";
+
+        let menus = parse_weekly_menu(text, week_start);
+
+        assert_eq!(menus["2026-01-31-brunch"], "Full English breakfast");
+        assert_eq!(menus["2026-02-01-brunch"], "Pancakes and berries");
+    }
+
+    #[test]
+    fn test_parse_weekly_menu_brunch_headers() {
+        let week_start = NaiveDate::from_ymd_opt(2026, 1, 26).unwrap();
+
+        // One header per day, and the word repeated several times in a header.
+        let text = "Saturday Brunch Brunch Brunch\nWaffles\nSunday Brunch\nEggs Benedict\n";
+        let menus = parse_weekly_menu(text, week_start);
+        assert_eq!(menus["2026-01-31-brunch"], "Waffles");
+        assert_eq!(menus["2026-02-01-brunch"], "Eggs Benedict");
+
+        // Dishes that mention brunch stay in the section rather than opening a new one.
+        let text = "Brunch\nFull brunch platter\nBrunch-style pancakes\n";
+        let menus = parse_weekly_menu(text, week_start);
+        assert_eq!(menus["2026-01-31-brunch"], "Full brunch platter");
+        assert_eq!(menus["2026-02-01-brunch"], "Brunch-style pancakes");
+
+        // A single block with no column split is served on both days.
+        let menus = parse_weekly_menu("Brunch\nHot buffet\n", week_start);
+        assert_eq!(menus["2026-01-31-brunch"], "Hot buffet");
+        assert_eq!(menus["2026-02-01-brunch"], "Hot buffet");
+    }
+
+    #[test]
+    fn test_week_coverage_reports_missing_slots() {
+        let week_start = NaiveDate::from_ymd_opt(2026, 1, 26).unwrap();
+        let mut menus = HashMap::new();
+        menus.insert("2026-01-26-lunch".to_string(), "Pasta".to_string());
+        menus.insert("2026-01-27-lunch".to_string(), "Curry".to_string());
+        menus.insert("2026-02-01-dinner".to_string(), "Roast".to_string());
+
+        let coverage = week_coverage(&menus, week_start);
+
+        assert_eq!(
+            coverage.summary,
+            "breakfast 0/5, brunch 0/2, lunch 2/5, dinner 1/7"
+        );
+        assert!(coverage.missing.contains(&"2026-01-28-lunch".to_string()));
+        assert!(!coverage.missing.contains(&"2026-02-01-dinner".to_string()));
+        assert_eq!(coverage.missing.len(), 5 + 2 + 3 + 6);
+    }
+
+    #[test]
+    fn test_search_index_fuzzy() {
+        let mut index = HashMap::new();
+        index.insert(
+            "2026-01-26-lunch".to_string(),
+            "Spaghetti Bolognese\nGarlic bread".to_string(),
+        );
+        index.insert("2026-01-27-lunch".to_string(), "Beef Lasagne".to_string());
+        index.insert("2026-01-28-dinner".to_string(), "Fish pie".to_string());
+        index.insert(
+            "prep:2026-01-29-lunch".to_string(),
+            "Chicken curry".to_string(),
+        );
+
+        assert!(search_index(&index, "lasagna", false).is_empty());
+
+        let results = search_index(&index, "lasagna", true);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].date, "2026-01-27");
+        assert_eq!(results[0].period, "lunch");
+        assert_eq!(results[0].menu_type, DEFAULT_MENU_TYPE);
+
+        let results = search_index(&index, "curry", false);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].date, "2026-01-29");
+        assert_eq!(results[0].period, "lunch");
+        assert_eq!(results[0].menu_type, "prep");
+
+        let results = search_index(&index, "spag bol", true);
+        assert_eq!(results.len(), 1);
+        assert!(results[0].score > 0.8);
+
+        let results = search_index(&index, "fish", false);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].score, 1.0);
+    }
+
+    #[test]
+    fn test_extract_menu_links_skips_off_host_links() {
+        let html = r#"
+            <a href="/wp-content/uploads/Menu-w-c-26-01.pdf">Menu for w/c Monday 26th January 2026</a>
+            <a href="http://www.cranbrookschool.co.uk/menu-insecure.pdf">Menu</a>
+            <a href="https://evil.example.com/menu.pdf">Menu</a>
+            <a href="https://cdn.example.com/menu-cdn.pdf">Menu</a>
+        "#;
+        let base = Url::parse(BASE_URL).unwrap();
+        let hosts = vec!["www.cranbrookschool.co.uk".to_string()];
+
+        let links = extract_menu_links(html, &base, &hosts);
+        assert_eq!(links.len(), 1);
+        assert_eq!(
+            links[0].0,
+            "https://www.cranbrookschool.co.uk/wp-content/uploads/Menu-w-c-26-01.pdf"
+        );
+        assert_eq!(links[0].1, NaiveDate::from_ymd_opt(2026, 1, 26));
+
+        let hosts = vec![
+            "www.cranbrookschool.co.uk".to_string(),
+            "cdn.example.com".to_string(),
+        ];
+        assert_eq!(extract_menu_links(html, &base, &hosts).len(), 2);
+    }
+
+    #[test]
+    fn test_today_in_timezone() {
+        let at = |raw: &str| {
+            chrono::DateTime::parse_from_rfc3339(raw)
+                .unwrap()
+                .with_timezone(&chrono::Utc)
+        };
+        let date = |month, day| NaiveDate::from_ymd_opt(2026, month, day).unwrap();
+
+        // 23:30 UTC in June is already tomorrow in BST, but not in January's GMT.
+        assert_eq!(
+            today_in("Europe/London", at("2026-06-15T23:30:00Z")),
+            date(6, 16)
+        );
+        assert_eq!(
+            today_in("Europe/London", at("2026-01-15T23:30:00Z")),
+            date(1, 15)
+        );
+        // BST ends at 01:00 UTC on the last Sunday of October.
+        assert_eq!(
+            today_in("Europe/London", at("2026-10-24T23:30:00Z")),
+            date(10, 25)
+        );
+        assert_eq!(
+            today_in("Europe/London", at("2026-10-25T23:30:00Z")),
+            date(10, 25)
+        );
+
+        assert_eq!(today_in("UTC", at("2026-06-15T23:30:00Z")), date(6, 15));
+        assert_eq!(today_in("-05:00", at("2026-06-15T02:00:00Z")), date(6, 14));
+        assert_eq!(
+            today_in("Mars/Olympus", at("2026-06-15T23:30:00Z")),
+            date(6, 15)
+        );
+    }
+
+    #[test]
+    fn test_user_agent_and_contact_header() {
+        assert_eq!(user_agent_for(Some(" my-bot/2 ")), "my-bot/2");
+        assert!(user_agent_for(Some("")).starts_with(&format!("{DEFAULT_USER_AGENT} (v")));
+        assert!(user_agent_for(None).starts_with(DEFAULT_USER_AGENT));
+
+        assert_eq!(
+            contact_header(Some("catering@example.com")).unwrap(),
+            "catering@example.com"
+        );
+        assert!(contact_header(Some("not an email")).is_none());
+        assert!(contact_header(Some("bad\n@example.com")).is_none());
+        assert!(contact_header(None).is_none());
+    }
+
+    #[test]
+    fn test_parse_cors_origins() {
+        assert!(parse_cors_origins("*").unwrap().is_none());
+        assert!(parse_cors_origins("").unwrap().is_none());
+
+        let origins = parse_cors_origins("https://example.com/, http://localhost:5173")
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            origins,
+            vec!["https://example.com", "http://localhost:5173"]
+        );
+
+        assert!(parse_cors_origins("example.com").is_err());
+    }
 }
@@ -1,8 +1,23 @@
 use cargo_lambda_macro::lambda_function;
+use chrono::NaiveDate;
 use lambda_runtime::{Error, LambdaEvent};
 use serde_json::{Value, json};
+use tokio::sync::OnceCell;
 
-use cranbrook_catering_api::{fetch_meal_for_date, parse_date_param, parse_payload};
+use cranbrook_catering_api::{
+    MealPeriod, NO_MENUS_MESSAGE, fetch_meal_for_date, format_output_date, init_tracing,
+    is_not_yet_published, is_scheduled_event, no_menus_found, not_served_reason, param_max_len,
+    parse_payload, preload_current_weeks, sanitize_param, today, try_parse_date_param,
+};
+#[cfg(feature = "s3")]
+use cranbrook_catering_api::{
+    config::config,
+    run_scheduled_refresh,
+    store::{MenuStore, S3MenuStore},
+    stored_meal_lookup,
+};
+
+static PRELOAD: OnceCell<()> = OnceCell::const_new();
 
 fn build_response(status: u16, body: Value) -> Value {
     json!({
@@ -12,53 +27,179 @@ fn build_response(status: u16, body: Value) -> Value {
     })
 }
 
+/// The S3 index store, when built with `s3` and `INDEX_BUCKET` is set.
+#[cfg(feature = "s3")]
+async fn s3_store() -> Option<&'static S3MenuStore> {
+    static STORE: OnceCell<Option<S3MenuStore>> = OnceCell::const_new();
+    STORE
+        .get_or_init(|| async {
+            let config = config();
+            if config.index_bucket.is_empty() {
+                return None;
+            }
+            Some(S3MenuStore::from_env(&config.index_bucket, &config.index_key).await)
+        })
+        .await
+        .as_ref()
+}
+
+/// Looks the meal up in the index written by the scheduled refresh. `None`
+/// when there is no index to answer from, so the caller scrapes instead.
+#[cfg(feature = "s3")]
+async fn stored_meal(
+    date: NaiveDate,
+    period: &str,
+    fallback_prev: bool,
+) -> Option<anyhow::Result<Option<String>>> {
+    let store = s3_store().await?;
+    match store.load().await {
+        Ok(index) => Some(
+            stored_meal_lookup(&index?, date, period, fallback_prev, today()).map_err(Into::into),
+        ),
+        Err(err) => {
+            tracing::warn!("Failed to load the stored index: {err}");
+            None
+        }
+    }
+}
+
+#[cfg(not(feature = "s3"))]
+async fn stored_meal(
+    _date: NaiveDate,
+    _period: &str,
+    _fallback_prev: bool,
+) -> Option<anyhow::Result<Option<String>>> {
+    None
+}
+
 #[lambda_function]
 async fn func(event: LambdaEvent<Value>) -> Result<Value, Error> {
     // Run blocking network/PDF work on a dedicated blocking thread to avoid
     // dropping a nested Tokio runtime inside async context.
 
-    let date_raw = parse_payload::<String>(&event, "date");
-    let period_raw = parse_payload::<String>(&event, "period");
+    // EventBridge schedule: rebuild the stored index instead of serving a request.
+    if is_scheduled_event(&event.payload) {
+        init_tracing();
+        #[cfg(feature = "s3")]
+        if let Some(store) = s3_store().await {
+            let summary = run_scheduled_refresh(store).await?;
+            return Ok(serde_json::to_value(summary)?);
+        }
+        // Local disk isn't shared between Lambda instances, so an index
+        // written here would never be read.
+        return Err(
+            "Scheduled refresh needs a shared index store: build with the s3 feature and set INDEX_BUCKET"
+                .into(),
+        );
+    }
 
-    let date_raw = match date_raw {
-        Some(value) => value,
-        None => chrono::Local::now().format("%Y-%m-%d").to_string(),
+    // Cold start: warm the cache with the weeks most likely to be queried.
+    PRELOAD
+        .get_or_init(|| async {
+            init_tracing();
+            // Requests are answered from the stored index; no need to scrape.
+            #[cfg(feature = "s3")]
+            if s3_store().await.is_some() {
+                return;
+            }
+            if let Err(err) = preload_current_weeks().await {
+                tracing::warn!("Preload failed: {err}");
+            }
+        })
+        .await;
+
+    let sanitized = ["date", "period", "fallback"].map(|name| {
+        parse_payload::<String>(&event, name)
+            .map(|value| sanitize_param(name, &value, param_max_len(name)))
+            .transpose()
+    });
+    let [date_raw, period_raw, fallback_raw] = match sanitized {
+        [Ok(date), Ok(period), Ok(fallback)] => [date, period, fallback],
+        [Err(message), _, _] | [_, Err(message), _] | [_, _, Err(message)] => {
+            return Ok(build_response(400, json!({ "error": message })));
+        }
     };
-    let period = match period_raw {
-        Some(value) => value.to_lowercase(),
-        None => {
+    let fallback_prev = match fallback_raw.as_deref().map(str::trim) {
+        None | Some("") => false,
+        Some(value) if value.eq_ignore_ascii_case("prev") => true,
+        Some(_) => {
             return Ok(build_response(
                 400,
-                json!({ "error": "Missing required 'period' parameter." }),
+                json!({ "error": "Unrecognized fallback. Use fallback=prev." }),
             ));
         }
     };
 
-    let date = match parse_date_param(&date_raw) {
-        Some(value) => value,
+    let date_raw = match date_raw {
+        Some(value) => value.trim().to_string(),
+        None => today().format("%Y-%m-%d").to_string(),
+    };
+    let period = match period_raw {
+        Some(value) => match value.trim().trim_end_matches('/').parse::<MealPeriod>() {
+            Ok(period) => period.as_str().to_string(),
+            Err(()) => {
+                return Ok(build_response(
+                    400,
+                    json!({ "error": format!(
+                        "Unrecognized period: {}. Use breakfast, break, brunch, lunch or dinner.",
+                        value.trim()
+                    ) }),
+                ));
+            }
+        },
         None => {
             return Ok(build_response(
                 400,
-                json!({ "error": "Invalid date format. Use YYYY-MM-DD or YYYY/MM/DD." }),
+                json!({ "error": "Missing required 'period' parameter." }),
             ));
         }
     };
 
-    let fetched = fetch_meal_for_date(date, &period).await;
+    let date = match try_parse_date_param(&date_raw) {
+        Ok(value) => value,
+        Err(err) => {
+            return Ok(build_response(400, json!({ "error": err.to_string() })));
+        }
+    };
+
+    if let Some(message) = not_served_reason(&period, date) {
+        return Ok(build_response(
+            404,
+            json!({ "status": "not_served", "error": message }),
+        ));
+    }
+
+    // With a stored index, a miss is answered from it rather than by scraping.
+    let fetched = match stored_meal(date, &period, fallback_prev).await {
+        Some(stored) => stored,
+        None => fetch_meal_for_date(date, &period).await,
+    };
 
     match fetched {
         Ok(Some(meal)) => Ok(build_response(
             200,
             json!({
-                "date": date_raw,
+                "date": format_output_date(date),
                 "period": period,
                 "meal": meal,
             }),
         )),
+        Ok(None) if no_menus_found() => Ok(build_response(
+            503,
+            json!({ "status": "no_menus_found", "error": NO_MENUS_MESSAGE }),
+        )),
         Ok(None) => Ok(build_response(
             404,
             json!({ "error": "Meal not found for requested date/period." }),
         )),
+        Err(err) if is_not_yet_published(&err) => Ok(build_response(
+            200,
+            json!({
+                "status": "pending",
+                "date": format_output_date(date),
+                "message": "Menu for this week hasn't been published yet",
+            }),
+        )),
         Err(err) => Ok(build_response(
             500,
             json!({ "error": format!("Failed to fetch menu data: {err}") }),
@@ -69,7 +210,6 @@ async fn func(event: LambdaEvent<Value>) -> Result<Value, Error> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use chrono::NaiveDate;
 
     const DATE: NaiveDate = NaiveDate::from_ymd_opt(2026, 2, 12).unwrap();
 
@@ -0,0 +1,107 @@
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{HeaderName, HeaderValue, header},
+    middleware::Next,
+    response::Response,
+};
+use serde_json::Value;
+use tracing::Instrument;
+
+pub static REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+/// The ID of the current request, available to handlers as a request extension.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RequestId(pub String);
+
+/// Reuses a sane incoming `X-Request-Id` or generates a UUID.
+pub fn request_id_from(value: Option<&HeaderValue>) -> String {
+    value
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim)
+        .filter(|value| {
+            !value.is_empty() && value.len() <= 128 && value.chars().all(|c| c.is_ascii_graphic())
+        })
+        .map(str::to_string)
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string())
+}
+
+/// Tags every request with an ID, runs it inside a span carrying the ID, and
+/// echoes the ID in the `X-Request-Id` response header.
+pub async fn propagate_request_id(mut request: Request, next: Next) -> Response {
+    let id = request_id_from(request.headers().get(&REQUEST_ID_HEADER));
+    request.extensions_mut().insert(RequestId(id.clone()));
+
+    let span = tracing::info_span!(
+        "request",
+        request_id = %id,
+        method = %request.method(),
+        path = %request.uri().path(),
+    );
+    let mut response = next.run(request).instrument(span).await;
+
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        response
+            .headers_mut()
+            .insert(REQUEST_ID_HEADER.clone(), value);
+    }
+    response
+}
+
+/// Adds the request ID to error bodies, wrapping plain-text errors as
+/// `{"error": ...}` so every error is JSON. Mounted as a route layer so it runs
+/// before HEAD responses have their body stripped.
+pub async fn add_request_id_to_errors(request: Request, next: Next) -> Response {
+    let id = request.extensions().get::<RequestId>().cloned();
+    let response = next.run(request).await;
+    let status = response.status();
+    match id {
+        Some(RequestId(id)) if status.is_client_error() || status.is_server_error() => {
+            add_id_to_error_body(response, &id).await
+        }
+        _ => response,
+    }
+}
+
+fn is_json(response: &Response) -> bool {
+    response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/json"))
+}
+
+async fn add_id_to_error_body(response: Response, id: &str) -> Response {
+    let json = is_json(&response);
+    let (mut parts, body) = response.into_parts();
+    let bytes = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(_) => return Response::from_parts(parts, Body::empty()),
+    };
+
+    let mut map = if json {
+        match serde_json::from_slice::<Value>(&bytes) {
+            Ok(Value::Object(map)) => map,
+            _ => return Response::from_parts(parts, Body::from(bytes)),
+        }
+    } else {
+        let text = String::from_utf8_lossy(&bytes);
+        let message = if text.trim().is_empty() {
+            parts.status.canonical_reason().unwrap_or_default()
+        } else {
+            text.trim()
+        };
+        let mut map = serde_json::Map::new();
+        map.insert("error".to_string(), Value::String(message.to_string()));
+        map
+    };
+
+    map.insert("request_id".to_string(), Value::String(id.to_string()));
+    let body = serde_json::to_vec(&Value::Object(map)).unwrap_or_else(|_| bytes.to_vec());
+    parts.headers.remove(header::CONTENT_LENGTH);
+    parts.headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/json"),
+    );
+    Response::from_parts(parts, Body::from(body))
+}
@@ -0,0 +1,72 @@
+//! Where a built index is persisted between the scheduled scrape and the
+//! request-serving functions.
+
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+#[cfg(feature = "s3")]
+mod s3;
+#[cfg(feature = "s3")]
+pub use s3::S3MenuStore;
+
+pub type Index = HashMap<String, String>;
+
+pub trait MenuStore {
+    /// The stored index, or `None` if nothing has been written yet. Shared,
+    /// so stores that cache it can hand it out without copying.
+    fn load(&self) -> impl Future<Output = anyhow::Result<Option<Arc<Index>>>> + Send;
+
+    fn store(&self, index: &Index) -> impl Future<Output = anyhow::Result<()>> + Send;
+}
+
+/// Keeps the index as a JSON file on local disk.
+#[derive(Clone, Debug)]
+pub struct FileMenuStore {
+    pub path: PathBuf,
+}
+
+impl FileMenuStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        FileMenuStore { path: path.into() }
+    }
+}
+
+impl MenuStore for FileMenuStore {
+    async fn load(&self) -> anyhow::Result<Option<Arc<Index>>> {
+        match tokio::fs::read(&self.path).await {
+            Ok(bytes) => Ok(Some(Arc::new(serde_json::from_slice(&bytes)?))),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn store(&self, index: &Index) -> anyhow::Result<()> {
+        Ok(crate::write_atomically(&self.path, &index_json(index)?).await?)
+    }
+}
+
+/// Pretty JSON with sorted keys, so the output is stable between runs.
+pub fn index_json(index: &Index) -> serde_json::Result<Vec<u8>> {
+    let sorted: BTreeMap<_, _> = index.iter().collect();
+    serde_json::to_vec_pretty(&sorted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_file_store_round_trip() {
+        let dir = std::env::temp_dir().join(format!("catering-store-{}", std::process::id()));
+        let store = FileMenuStore::new(dir.join("index.json"));
+        assert!(store.load().await.unwrap().is_none());
+
+        let index = Index::from([("2026-01-26-lunch".to_string(), "Curry".to_string())]);
+        store.store(&index).await.unwrap();
+        assert_eq!(store.load().await.unwrap().as_deref(), Some(&index));
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
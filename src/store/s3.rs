@@ -0,0 +1,218 @@
+//! Index storage in a single S3 object, for the Lambda deployment.
+
+use super::{Index, MenuStore, index_json};
+use aws_sdk_s3::{Client, operation::head_object::HeadObjectError, primitives::ByteStream};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// How long a loaded index is served before its ETag is checked again.
+pub const ETAG_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+struct CachedIndex {
+    etag: String,
+    index: Arc<Index>,
+    checked_at: Instant,
+}
+
+pub struct S3MenuStore {
+    client: Client,
+    bucket: String,
+    key: String,
+    check_interval: Duration,
+    /// The object last read or written, with its ETag.
+    cached: Mutex<Option<CachedIndex>>,
+}
+
+impl S3MenuStore {
+    pub fn new(client: Client, bucket: impl Into<String>, key: impl Into<String>) -> Self {
+        S3MenuStore {
+            client,
+            bucket: bucket.into(),
+            key: key.into(),
+            check_interval: ETAG_CHECK_INTERVAL,
+            cached: Mutex::new(None),
+        }
+    }
+
+    /// Overrides [`ETAG_CHECK_INTERVAL`].
+    pub fn with_check_interval(mut self, interval: Duration) -> Self {
+        self.check_interval = interval;
+        self
+    }
+
+    /// A store using the default AWS credentials and region chain.
+    pub async fn from_env(bucket: impl Into<String>, key: impl Into<String>) -> Self {
+        let config = aws_config::load_defaults(aws_config::BehaviorVersion::latest()).await;
+        Self::new(Client::new(&config), bucket, key)
+    }
+
+    fn lock_cached(&self) -> std::sync::MutexGuard<'_, Option<CachedIndex>> {
+        self.cached
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// The cached index if its ETag was checked within the interval.
+    fn fresh(&self) -> Option<Arc<Index>> {
+        self.lock_cached()
+            .as_ref()
+            .filter(|cached| cached.checked_at.elapsed() < self.check_interval)
+            .map(|cached| cached.index.clone())
+    }
+
+    /// The cached index if it still has `etag`, marking it as just checked.
+    fn revalidate(&self, etag: &str) -> Option<Arc<Index>> {
+        let mut cached = self.lock_cached();
+        let cached = cached.as_mut().filter(|cached| cached.etag == etag)?;
+        cached.checked_at = Instant::now();
+        Some(cached.index.clone())
+    }
+
+    fn remember(&self, etag: String, index: Arc<Index>) {
+        *self.lock_cached() = Some(CachedIndex {
+            etag,
+            index,
+            checked_at: Instant::now(),
+        });
+    }
+}
+
+impl MenuStore for S3MenuStore {
+    /// Serves the cached index for [`ETAG_CHECK_INTERVAL`], then checks the
+    /// object's ETag and only downloads it when it has changed.
+    async fn load(&self) -> anyhow::Result<Option<Arc<Index>>> {
+        if let Some(index) = self.fresh() {
+            return Ok(Some(index));
+        }
+        let head = self
+            .client
+            .head_object()
+            .bucket(&self.bucket)
+            .key(&self.key)
+            .send()
+            .await;
+        let etag = match head {
+            Ok(head) => head.e_tag().unwrap_or_default().to_string(),
+            Err(err) if matches!(err.as_service_error(), Some(HeadObjectError::NotFound(_))) => {
+                return Ok(None);
+            }
+            Err(err) => return Err(err.into()),
+        };
+        if let Some(index) = self.revalidate(&etag) {
+            return Ok(Some(index));
+        }
+
+        let object = self
+            .client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(&self.key)
+            .send()
+            .await?;
+        let etag = object.e_tag().map(str::to_string).unwrap_or(etag);
+        let bytes = object.body.collect().await?.into_bytes();
+        let index: Arc<Index> = Arc::new(serde_json::from_slice(&bytes)?);
+        self.remember(etag, index.clone());
+        Ok(Some(index))
+    }
+
+    async fn store(&self, index: &Index) -> anyhow::Result<()> {
+        let output = self
+            .client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(&self.key)
+            .content_type("application/json")
+            .body(ByteStream::from(index_json(index)?))
+            .send()
+            .await?;
+        if let Some(etag) = output.e_tag() {
+            self.remember(etag.to_string(), Arc::new(index.clone()));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aws_sdk_s3::operation::{
+        get_object::GetObjectOutput, head_object::HeadObjectOutput, put_object::PutObjectOutput,
+    };
+    use aws_sdk_s3::types::error::NotFound;
+    use aws_smithy_mocks::{RuleMode, mock, mock_client};
+
+    const BODY: &[u8] = br#"{"2026-01-26-lunch": "Curry"}"#;
+
+    fn index() -> Index {
+        Index::from([("2026-01-26-lunch".to_string(), "Curry".to_string())])
+    }
+
+    #[tokio::test]
+    async fn test_load_downloads_only_when_etag_changes() {
+        let head = mock!(Client::head_object)
+            .then_output(|| HeadObjectOutput::builder().e_tag("\"v1\"").build());
+        let get = mock!(Client::get_object).then_output(|| {
+            GetObjectOutput::builder()
+                .e_tag("\"v1\"")
+                .body(ByteStream::from_static(BODY))
+                .build()
+        });
+        let client = mock_client!(aws_sdk_s3, RuleMode::MatchAny, [&head, &get]);
+        let store =
+            S3MenuStore::new(client, "menus", "index.json").with_check_interval(Duration::ZERO);
+
+        assert_eq!(store.load().await.unwrap().as_deref(), Some(&index()));
+        assert_eq!(store.load().await.unwrap().as_deref(), Some(&index()));
+        assert_eq!(head.num_calls(), 2);
+        assert_eq!(get.num_calls(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_load_skips_etag_check_within_interval() {
+        let head = mock!(Client::head_object)
+            .then_output(|| HeadObjectOutput::builder().e_tag("\"v1\"").build());
+        let get = mock!(Client::get_object).then_output(|| {
+            GetObjectOutput::builder()
+                .e_tag("\"v1\"")
+                .body(ByteStream::from_static(BODY))
+                .build()
+        });
+        let client = mock_client!(aws_sdk_s3, RuleMode::MatchAny, [&head, &get]);
+        let store = S3MenuStore::new(client, "menus", "index.json");
+
+        let first = store.load().await.unwrap().unwrap();
+        let second = store.load().await.unwrap().unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+        assert_eq!(head.num_calls(), 1);
+        assert_eq!(get.num_calls(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_load_missing_object_is_none() {
+        let head = mock!(Client::head_object)
+            .then_error(|| HeadObjectError::NotFound(NotFound::builder().build()));
+        let client = mock_client!(aws_sdk_s3, [&head]);
+        let store = S3MenuStore::new(client, "menus", "index.json");
+
+        assert_eq!(store.load().await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_store_writes_json_and_primes_cache() {
+        let put = mock!(Client::put_object)
+            .match_requests(|req| req.bucket() == Some("menus") && req.key() == Some("index.json"))
+            .then_output(|| PutObjectOutput::builder().e_tag("\"v2\"").build());
+        let head = mock!(Client::head_object)
+            .then_output(|| HeadObjectOutput::builder().e_tag("\"v2\"").build());
+        let get = mock!(Client::get_object).then_output(|| GetObjectOutput::builder().build());
+        let client = mock_client!(aws_sdk_s3, RuleMode::MatchAny, [&put, &head, &get]);
+        let store = S3MenuStore::new(client, "menus", "index.json");
+
+        store.store(&index()).await.unwrap();
+        assert_eq!(store.load().await.unwrap().as_deref(), Some(&index()));
+        assert_eq!(put.num_calls(), 1);
+        assert_eq!(head.num_calls(), 0);
+        assert_eq!(get.num_calls(), 0);
+    }
+}
@@ -10,11 +10,25 @@ export default $config({
     };
   },
   async run() {
-    new sst.aws.Function("main", {
+    // The scheduled refresh writes the index here and requests read it back;
+    // the S3 store is built in by the crate's default `s3` feature.
+    const index = new sst.aws.Bucket("index");
+
+    const main = new sst.aws.Function("main", {
       url: true,
       runtime: "rust",
       handler: ".main",
       architecture: "arm64",
+      link: [index],
+      environment: {
+        INDEX_BUCKET: index.name,
+      },
+    });
+
+    // Scheduled events are detected by shape and run the full scrape.
+    new sst.aws.Cron("refresh", {
+      schedule: "rate(1 hour)",
+      function: main.arn,
     });
   },
 });